@@ -0,0 +1,174 @@
+use anyhow::{Context, Result};
+use ignore::WalkBuilder;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+
+use crate::cargo_discovery::read_manifest;
+
+/// A non-local package listed in Cargo.lock
+#[derive(Debug)]
+pub struct LockedPackage {
+    pub name: String,
+    pub version: String,
+    /// `registry+...`, `sparse+...` or `git+...#<commit>`
+    pub source: String,
+}
+
+/// Where the locked dependencies of a workspace were found on disk
+#[derive(Debug, Default)]
+pub struct DependencySources {
+    /// Cargo.toml of each dependency whose source is available locally
+    pub manifests: Vec<PathBuf>,
+    /// Dependencies that have not been fetched or vendored
+    pub missing: Vec<LockedPackage>,
+}
+
+/// Resolve the dependencies recorded in the Cargo.lock of the workspace that
+/// contains `manifest_path`, without touching the network. Sources are looked
+/// up in the workspace's `vendor/` directory first, then in Cargo's registry
+/// and git checkout caches under `$CARGO_HOME`.
+pub fn locate(manifest_path: &Path) -> Result<DependencySources> {
+    let lock_path = find_lock_file(manifest_path)?;
+    let workspace_dir = lock_path.parent().unwrap_or(Path::new(""));
+    let vendor_dir = workspace_dir.join("vendor");
+    let cargo_home = cargo_home();
+
+    let mut sources = DependencySources::default();
+    for package in locked_packages(&lock_path)? {
+        let found = find_vendored(&vendor_dir, &package)
+            .or_else(|| cargo_home.as_deref().and_then(|home| find_in_cargo_home(home, &package)));
+        match found {
+            Some(manifest) => sources.manifests.push(manifest),
+            None => sources.missing.push(package),
+        }
+    }
+    Ok(sources)
+}
+
+/// Cargo.lock lives at the workspace root: the manifest's directory or one of its ancestors
+fn find_lock_file(manifest_path: &Path) -> Result<PathBuf> {
+    let manifest_path = manifest_path
+        .canonicalize()
+        .with_context(|| format!("Failed to find manifest: {}", manifest_path.display()))?;
+    manifest_path
+        .ancestors()
+        .skip(1)
+        .map(|dir| dir.join("Cargo.lock"))
+        .find(|lock| lock.is_file())
+        .with_context(|| format!("No Cargo.lock found for {} (run `cargo generate-lockfile`)", manifest_path.display()))
+}
+
+fn locked_packages(lock_path: &Path) -> Result<Vec<LockedPackage>> {
+    let content = fs::read_to_string(lock_path)
+        .with_context(|| format!("Failed to read lock file: {}", lock_path.display()))?;
+    let lock: Table = content
+        .parse()
+        .with_context(|| format!("Failed to parse lock file: {}", lock_path.display()))?;
+
+    let packages = lock
+        .get("package")
+        .and_then(Value::as_array)
+        .map(|packages| packages.as_slice())
+        .unwrap_or_default();
+    Ok(packages
+        .iter()
+        .filter_map(|package| {
+            let field = |key: &str| package.get(key).and_then(Value::as_str).map(str::to_string);
+            // Workspace members and path dependencies have no source
+            Some(LockedPackage {
+                name: field("name")?,
+                version: field("version")?,
+                source: field("source")?,
+            })
+        })
+        .collect())
+}
+
+fn cargo_home() -> Option<PathBuf> {
+    env::var_os("CARGO_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".cargo")))
+}
+
+/// `cargo vendor` uses `vendor/<name>`, or `vendor/<name>-<version>` when
+/// several versions of a crate are locked
+fn find_vendored(vendor_dir: &Path, package: &LockedPackage) -> Option<PathBuf> {
+    let versioned = vendor_dir
+        .join(format!("{}-{}", package.name, package.version))
+        .join("Cargo.toml");
+    if versioned.is_file() {
+        return Some(versioned);
+    }
+    let plain = vendor_dir.join(&package.name).join("Cargo.toml");
+    (plain.is_file() && manifest_matches(&plain, package)).then_some(plain)
+}
+
+fn find_in_cargo_home(cargo_home: &Path, package: &LockedPackage) -> Option<PathBuf> {
+    if let Some((_, commit)) = package.source.strip_prefix("git+").and_then(|url| url.rsplit_once('#')) {
+        return find_git_checkout(&cargo_home.join("git/checkouts"), commit, package);
+    }
+
+    // registry/src/<index>-<hash>/<name>-<version>; the hash depends on the
+    // index URL, so every unpacked index is searched
+    let dir_name = format!("{}-{}", package.name, package.version);
+    let mut indexes: Vec<PathBuf> = fs::read_dir(cargo_home.join("registry/src"))
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .collect();
+    indexes.sort();
+    indexes
+        .into_iter()
+        .map(|index| index.join(&dir_name).join("Cargo.toml"))
+        .find(|manifest| manifest.is_file())
+}
+
+/// git/checkouts/<repo>-<hash>/<short commit>/ holds a whole repository, in
+/// which the package may live in any subdirectory
+fn find_git_checkout(checkouts: &Path, commit: &str, package: &LockedPackage) -> Option<PathBuf> {
+    let mut repos: Vec<PathBuf> = fs::read_dir(checkouts)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .collect();
+    repos.sort();
+
+    for repo in repos {
+        let Ok(revisions) = fs::read_dir(&repo) else {
+            continue;
+        };
+        for revision in revisions.flatten() {
+            let name = revision.file_name();
+            let name = name.to_string_lossy();
+            if name.is_empty() || !commit.starts_with(name.as_ref()) {
+                continue;
+            }
+            let found = WalkBuilder::new(revision.path())
+                .max_depth(Some(4))
+                .build()
+                .flatten()
+                .map(|entry| entry.into_path())
+                .filter(|path| path.file_name().is_some_and(|name| name == "Cargo.toml"))
+                .find(|manifest| manifest_matches(manifest, package));
+            if found.is_some() {
+                return found;
+            }
+        }
+    }
+    None
+}
+
+fn manifest_matches(manifest_path: &Path, package: &LockedPackage) -> bool {
+    let Ok(manifest) = read_manifest(manifest_path) else {
+        return false;
+    };
+    let Some(metadata) = manifest.get("package") else {
+        return false;
+    };
+    let name = metadata.get("name").and_then(Value::as_str);
+    // Workspace-inherited versions (`version.workspace = true`) cannot be checked here
+    let version = metadata.get("version").and_then(Value::as_str);
+    name == Some(package.name.as_str()) && version.is_none_or(|version| version == package.version)
+}
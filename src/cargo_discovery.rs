@@ -0,0 +1,421 @@
+use anyhow::{Context, Result};
+use globset::Glob;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+
+use crate::recovery::{self, VerusBodies};
+use crate::syntax_generations;
+use crate::tag_visitor::is_verus_macro;
+
+/// Find every source file that belongs to the crates of a Cargo manifest:
+/// its workspace members and local path dependencies, each target's crate
+/// root, and every file reached from a root through `mod` declarations.
+pub fn crate_files(manifest_path: &Path) -> Result<Vec<PathBuf>> {
+    let mut discovery = Discovery::default();
+    discovery.visit_manifest(manifest_path)?;
+    Ok(discovery.files)
+}
+
+/// Source files of a package's library target only, without its other
+/// targets or dependencies
+pub fn library_files(manifest_path: &Path) -> Result<Vec<PathBuf>> {
+    let manifest = read_manifest(manifest_path)?;
+    let dir = manifest_path.parent().unwrap_or(Path::new(""));
+    let mut discovery = Discovery::default();
+    discovery.visit_source(&lib_root(dir, &manifest), true)?;
+    Ok(discovery.files)
+}
+
+#[derive(Debug, Default)]
+struct Discovery {
+    files: Vec<PathBuf>,
+    seen_files: BTreeSet<PathBuf>,
+    seen_manifests: BTreeSet<PathBuf>,
+}
+
+/// A `mod name;` declaration found in a source file
+#[derive(Debug)]
+struct ModDecl {
+    name: String,
+    /// Value of a `#[path = "..."]` attribute, if any
+    path_attr: Option<String>,
+    /// Inline `mod a { mod b { ... } }` blocks enclosing the declaration
+    inline_dirs: Vec<String>,
+}
+
+impl Discovery {
+    fn visit_manifest(&mut self, manifest_path: &Path) -> Result<()> {
+        let canonical = manifest_path
+            .canonicalize()
+            .with_context(|| format!("Failed to find manifest: {}", manifest_path.display()))?;
+        if !self.seen_manifests.insert(canonical) {
+            return Ok(());
+        }
+
+        let manifest = read_manifest(manifest_path)?;
+        let dir = manifest_path.parent().unwrap_or(Path::new(""));
+
+        if manifest.contains_key("package") {
+            for root in crate_roots(dir, &manifest) {
+                self.visit_source(&root, true)?;
+            }
+        }
+
+        if let Some(workspace) = manifest.get("workspace").and_then(Value::as_table) {
+            let excluded: Vec<PathBuf> = string_list(workspace.get("exclude"))
+                .iter()
+                .map(|path| dir.join(path))
+                .collect();
+            for pattern in string_list(workspace.get("members")) {
+                for member in expand_member_glob(dir, &pattern)? {
+                    if excluded.contains(&member) {
+                        continue;
+                    }
+                    let member_manifest = member.join("Cargo.toml");
+                    if member_manifest.is_file() {
+                        self.visit_manifest(&member_manifest)?;
+                    }
+                }
+            }
+            self.visit_path_dependencies(dir, workspace.get("dependencies"))?;
+        }
+
+        for section in ["dependencies", "dev-dependencies", "build-dependencies"] {
+            self.visit_path_dependencies(dir, manifest.get(section))?;
+        }
+        if let Some(targets) = manifest.get("target").and_then(Value::as_table) {
+            for target in targets.values().filter_map(Value::as_table) {
+                for section in ["dependencies", "dev-dependencies", "build-dependencies"] {
+                    self.visit_path_dependencies(dir, target.get(section))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn visit_path_dependencies(&mut self, dir: &Path, deps: Option<&Value>) -> Result<()> {
+        let Some(deps) = deps.and_then(Value::as_table) else {
+            return Ok(());
+        };
+        for dep in deps.values() {
+            if let Some(path) = dep.get("path").and_then(Value::as_str) {
+                let dep_manifest = dir.join(path).join("Cargo.toml");
+                if dep_manifest.is_file() {
+                    self.visit_manifest(&dep_manifest)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Add a source file and follow its `mod` declarations. Crate roots, mod.rs
+    /// files and files loaded through `#[path]` keep their children beside them.
+    fn visit_source(&mut self, path: &Path, mod_root: bool) -> Result<()> {
+        let canonical = match path.canonicalize() {
+            Ok(canonical) => canonical,
+            // Declared modules whose files are missing are not our problem
+            Err(_) => return Ok(()),
+        };
+        if !self.seen_files.insert(canonical) {
+            return Ok(());
+        }
+        self.files.push(path.to_path_buf());
+
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read file: {}", path.display()))?;
+        let mod_dir = module_dir(path, mod_root);
+        for decl in mod_decls(&content) {
+            for candidate in decl.candidates(path, &mod_dir) {
+                if candidate.is_file() {
+                    let child_is_root = decl.path_attr.is_some()
+                        || candidate.file_name().is_some_and(|name| name == "mod.rs");
+                    self.visit_source(&candidate, child_is_root)?;
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ModDecl {
+    /// Files that may hold this module, in the order rustc tries them
+    fn candidates(&self, file: &Path, mod_dir: &Path) -> Vec<PathBuf> {
+        let file_dir = file.parent().unwrap_or(Path::new(""));
+        let inline: PathBuf = self.inline_dirs.iter().collect();
+
+        if let Some(path_attr) = &self.path_attr {
+            // Outside inline modules, #[path] is relative to the declaring file's directory
+            let base = if self.inline_dirs.is_empty() {
+                file_dir.to_path_buf()
+            } else {
+                mod_dir.join(&inline)
+            };
+            return vec![base.join(path_attr)];
+        }
+
+        let base = mod_dir.join(&inline);
+        vec![
+            base.join(format!("{}.rs", self.name)),
+            base.join(&self.name).join("mod.rs"),
+        ]
+    }
+}
+
+/// Directory holding the children of a module file: the file's own directory
+/// for mod roots, otherwise a directory named after the file (`foo.rs` -> `foo/`)
+fn module_dir(path: &Path, mod_root: bool) -> PathBuf {
+    let dir = path.parent().unwrap_or(Path::new(""));
+    if mod_root {
+        return dir.to_path_buf();
+    }
+    match path.file_stem() {
+        Some(stem) => dir.join(stem),
+        None => dir.to_path_buf(),
+    }
+}
+
+pub fn read_manifest(manifest_path: &Path) -> Result<Table> {
+    let content = fs::read_to_string(manifest_path)
+        .with_context(|| format!("Failed to read manifest: {}", manifest_path.display()))?;
+    content
+        .parse()
+        .with_context(|| format!("Failed to parse manifest: {}", manifest_path.display()))
+}
+
+fn lib_root(dir: &Path, manifest: &Table) -> PathBuf {
+    match manifest
+        .get("lib")
+        .and_then(|lib| lib.get("path"))
+        .and_then(Value::as_str)
+    {
+        Some(path) => dir.join(path),
+        None => dir.join("src/lib.rs"),
+    }
+}
+
+/// Crate roots of every target in a package manifest
+fn crate_roots(dir: &Path, manifest: &Table) -> Vec<PathBuf> {
+    let mut roots = Vec::new();
+    let package = manifest.get("package").and_then(Value::as_table);
+    let auto = |key: &str| {
+        package
+            .and_then(|package| package.get(key))
+            .and_then(Value::as_bool)
+            .unwrap_or(true)
+    };
+
+    roots.push(lib_root(dir, manifest));
+
+    // Build script
+    match package
+        .and_then(|package| package.get("build"))
+        .and_then(Value::as_str)
+    {
+        Some(path) => roots.push(dir.join(path)),
+        None => roots.push(dir.join("build.rs")),
+    }
+
+    // Binaries, tests, examples and benches: explicit targets plus auto-discovered ones
+    let kinds = [
+        ("bin", "src/bin", "autobins"),
+        ("test", "tests", "autotests"),
+        ("example", "examples", "autoexamples"),
+        ("bench", "benches", "autobenches"),
+    ];
+    for (kind, default_dir, auto_key) in kinds {
+        if let Some(targets) = manifest.get(kind).and_then(Value::as_array) {
+            for target in targets {
+                if let Some(path) = target.get("path").and_then(Value::as_str) {
+                    roots.push(dir.join(path));
+                } else if let Some(name) = target.get("name").and_then(Value::as_str) {
+                    roots.push(dir.join(default_dir).join(format!("{}.rs", name)));
+                    roots.push(dir.join(default_dir).join(name).join("main.rs"));
+                }
+            }
+        }
+        if auto(auto_key) {
+            roots.extend(auto_targets(&dir.join(default_dir)));
+        }
+    }
+    if auto("autobins") {
+        roots.push(dir.join("src/main.rs"));
+    }
+
+    roots.retain(|root| root.is_file());
+    roots
+}
+
+/// `dir/*.rs` and `dir/*/main.rs`, sorted for stable output
+fn auto_targets(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut targets: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter_map(|path| {
+            if path.is_dir() {
+                Some(path.join("main.rs"))
+            } else if path.extension().is_some_and(|ext| ext == "rs") {
+                Some(path)
+            } else {
+                None
+            }
+        })
+        .filter(|path| path.is_file())
+        .collect();
+    targets.sort();
+    targets
+}
+
+/// Expand a workspace member pattern such as `crates/*` one path component at a time
+fn expand_member_glob(dir: &Path, pattern: &str) -> Result<Vec<PathBuf>> {
+    let mut matches = vec![dir.to_path_buf()];
+    for component in Path::new(pattern).components() {
+        let component = component.as_os_str().to_string_lossy();
+        let matcher = Glob::new(&component)
+            .with_context(|| format!("Invalid workspace member pattern: {}", pattern))?
+            .compile_matcher();
+        let mut next = Vec::new();
+        for base in &matches {
+            if !component.contains(['*', '?', '[']) {
+                next.push(base.join(component.as_ref()));
+                continue;
+            }
+            let Ok(entries) = fs::read_dir(base) else {
+                continue;
+            };
+            let mut found: Vec<PathBuf> = entries
+                .flatten()
+                .filter(|entry| matcher.is_match(entry.file_name()))
+                .map(|entry| entry.path())
+                .filter(|path| path.is_dir())
+                .collect();
+            found.sort();
+            next.extend(found);
+        }
+        matches = next;
+    }
+    Ok(matches)
+}
+
+fn string_list(value: Option<&Value>) -> Vec<String> {
+    value
+        .and_then(Value::as_array)
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// `mod name;` declarations in a file, including those inside verus! macros.
+/// A file with syntax errors is parsed the way it is tagged, item by item,
+/// so one broken item does not drop the module subtree of a crate root; the
+/// file itself is reported when it is tagged.
+fn mod_decls(content: &str) -> Vec<ModDecl> {
+    let mut decls = Vec::new();
+    let verus_decls = |items: &[verus_syn::Item], decls: &mut Vec<ModDecl>| {
+        verus_mod_decls(items, &VerusBodies::parse(items), &mut Vec::new(), decls);
+    };
+    if let Ok(file) = verus_syn::parse_file(content) {
+        verus_decls(&file.items, &mut decls);
+    } else if let Some((file, _)) = syntax_generations::parse_file_tolerant(content) {
+        verus_decls(&file.items, &mut decls);
+    } else if let Ok(file) = syn::parse_file(content) {
+        syn_mod_decls(&file.items, &mut Vec::new(), &mut decls);
+    } else {
+        let recovered = recovery::recover(content);
+        verus_decls(&recovered.verus_file.items, &mut decls);
+        syn_mod_decls(&recovered.syn_file.items, &mut Vec::new(), &mut decls);
+    }
+    decls
+}
+
+fn verus_mod_decls(
+    items: &[verus_syn::Item],
+    bodies: &VerusBodies,
+    inline_dirs: &mut Vec<String>,
+    decls: &mut Vec<ModDecl>,
+) {
+    for item in items {
+        match item {
+            verus_syn::Item::Mod(item_mod) => {
+                let path_attr = verus_path_attr(&item_mod.attrs);
+                match &item_mod.content {
+                    None => decls.push(ModDecl {
+                        name: item_mod.ident.to_string(),
+                        path_attr,
+                        inline_dirs: inline_dirs.clone(),
+                    }),
+                    Some((_, inner)) => {
+                        inline_dirs.push(path_attr.unwrap_or_else(|| item_mod.ident.to_string()));
+                        verus_mod_decls(inner, bodies, inline_dirs, decls);
+                        inline_dirs.pop();
+                    }
+                }
+            }
+            verus_syn::Item::Macro(item_macro) if is_verus_macro(&item_macro.mac) => {
+                verus_mod_decls(bodies.items(&item_macro.mac), bodies, inline_dirs, decls);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn verus_path_attr(attrs: &[verus_syn::Attribute]) -> Option<String> {
+    attrs.iter().find_map(|attr| {
+        if !attr.path().is_ident("path") {
+            return None;
+        }
+        match &attr.meta {
+            verus_syn::Meta::NameValue(name_value) => match &name_value.value {
+                verus_syn::Expr::Lit(verus_syn::ExprLit { lit: verus_syn::Lit::Str(path), .. }) => Some(path.value()),
+                _ => None,
+            },
+            _ => None,
+        }
+    })
+}
+
+fn syn_mod_decls(items: &[syn::Item], inline_dirs: &mut Vec<String>, decls: &mut Vec<ModDecl>) {
+    for item in items {
+        if let syn::Item::Mod(item_mod) = item {
+            let path_attr = syn_path_attr(&item_mod.attrs);
+            match &item_mod.content {
+                None => decls.push(ModDecl {
+                    name: item_mod.ident.to_string(),
+                    path_attr,
+                    inline_dirs: inline_dirs.clone(),
+                }),
+                Some((_, inner)) => {
+                    inline_dirs.push(path_attr.unwrap_or_else(|| item_mod.ident.to_string()));
+                    syn_mod_decls(inner, inline_dirs, decls);
+                    inline_dirs.pop();
+                }
+            }
+        }
+    }
+}
+
+fn syn_path_attr(attrs: &[syn::Attribute]) -> Option<String> {
+    attrs.iter().find_map(|attr| {
+        if !attr.path().is_ident("path") {
+            return None;
+        }
+        match &attr.meta {
+            syn::Meta::NameValue(name_value) => match &name_value.value {
+                syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(path), .. }) => Some(path.value()),
+                _ => None,
+            },
+            _ => None,
+        }
+    })
+}
@@ -0,0 +1,151 @@
+use serde_json::json;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::recovery::ItemError;
+use crate::syntax_generations::SyntaxChange;
+
+/// How the summary of skipped files is printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ErrorFormat {
+    /// `file:line:column: message` lines
+    Human,
+    /// One JSON object per skipped file
+    Json,
+}
+
+/// Why a file could not be tagged, with the parser's error position when known
+#[derive(Debug, Clone)]
+pub struct Failure {
+    pub message: String,
+    pub line: Option<usize>,
+    /// 1-based, as rustc reports columns
+    pub column: Option<usize>,
+    /// First lines of the items that could not be parsed, when the rest of
+    /// the file was tagged item by item
+    pub unparsed_lines: Vec<usize>,
+    /// Whether tags were recovered from the parts of the file that parse
+    pub recovered: bool,
+    /// Syntax of other Verus generations that the file was tagged without
+    pub syntax: Vec<SyntaxChange>,
+}
+
+impl Failure {
+    pub fn from_syn(error: &verus_syn::Error) -> Self {
+        let start = error.span().start();
+        Failure {
+            message: error.to_string(),
+            line: Some(start.line),
+            column: Some(start.column + 1),
+            unparsed_lines: Vec::new(),
+            recovered: false,
+            syntax: Vec::new(),
+        }
+    }
+
+    /// The failure of the first `verus!` body item verus_syn rejected, listing
+    /// the lines of the skipped items; `None` if every item parsed
+    pub fn from_item_errors(errors: &[ItemError]) -> Option<Self> {
+        let first = errors.first()?;
+        let mut failure = Failure {
+            unparsed_lines: errors
+                .iter()
+                .filter(|error| error.syntax.is_empty())
+                .map(|error| error.line)
+                .collect(),
+            recovered: true,
+            ..Failure::from_syn(&first.error)
+        };
+        failure.add_syntax(errors.iter().flat_map(|error| error.syntax.iter().copied()));
+        Some(failure)
+    }
+
+    pub fn add_syntax(&mut self, syntax: impl IntoIterator<Item = SyntaxChange>) {
+        for change in syntax {
+            if !self.syntax.contains(&change) {
+                self.syntax.push(change);
+            }
+        }
+    }
+
+    /// A parse failure carried by `error`, or its message if it is some other error
+    pub fn from_error(error: &anyhow::Error) -> Self {
+        error.downcast_ref::<Failure>().cloned().unwrap_or_else(|| Failure {
+            message: format!("{:#}", error),
+            line: None,
+            column: None,
+            unparsed_lines: Vec::new(),
+            recovered: false,
+            syntax: Vec::new(),
+        })
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for Failure {}
+
+/// Print every skipped file to stderr; nothing is printed when all files were tagged
+pub fn report_skipped(skipped: &[(PathBuf, Failure)], format: ErrorFormat) {
+    match format {
+        ErrorFormat::Human => {
+            let (recovered, lost): (Vec<_>, Vec<_>) =
+                skipped.iter().partition(|(_, failure)| failure.recovered);
+            if !lost.is_empty() {
+                eprintln!("Warning: skipped {} file(s) that could not be parsed:", lost.len());
+                for (path, failure) in lost {
+                    eprintln!("  {}", location(path, failure));
+                }
+            }
+            if !recovered.is_empty() {
+                eprintln!("Warning: tagged {} file(s) despite parse errors:", recovered.len());
+                for (path, failure) in recovered {
+                    if failure.unparsed_lines.is_empty() {
+                        eprintln!("  {}", location(path, failure));
+                    } else {
+                        let lines: Vec<String> = failure.unparsed_lines.iter().map(usize::to_string).collect();
+                        eprintln!("  {} (items skipped at lines {})", location(path, failure), lines.join(", "));
+                    }
+                    for change in &failure.syntax {
+                        eprintln!(
+                            "    note: `{}` needs Verus {} or later, newer than the bundled verus_syn; \
+                             tagged as if it were absent. Check out Verus {} or later in verus-lang/ to tag it exactly",
+                            change.construct(),
+                            change.first_release(),
+                            change.first_release()
+                        );
+                    }
+                }
+            }
+        }
+        ErrorFormat::Json => {
+            for (path, failure) in skipped {
+                let record = json!({
+                    "file": path,
+                    "line": failure.line,
+                    "column": failure.column,
+                    "message": failure.message,
+                    "recovered": failure.recovered,
+                    "unparsed_lines": failure.unparsed_lines,
+                    "syntax": failure.syntax.iter().map(|change| json!({
+                        "construct": change.construct(),
+                        "generation": "newer",
+                        "release": change.first_release(),
+                    })).collect::<Vec<_>>(),
+                });
+                eprintln!("{}", record);
+            }
+        }
+    }
+}
+
+fn location(path: &Path, failure: &Failure) -> String {
+    match (failure.line, failure.column) {
+        (Some(line), Some(column)) => format!("{}:{}:{}: {}", path.display(), line, column, failure.message),
+        _ => format!("{}: {}", path.display(), failure.message),
+    }
+}
@@ -0,0 +1,255 @@
+use anyhow::Result;
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::path_names::{self, PathStyle};
+use crate::tag_tree::{self, Node};
+use crate::tag_visitor::Tag;
+use crate::verus_index::VerusIndex;
+
+/// A definition or reference range, and the document it is in
+struct Occurrence {
+    document: u64,
+    range: u64,
+}
+
+/// Everything known about one name across the project
+#[derive(Default)]
+struct Symbol {
+    definitions: Vec<Occurrence>,
+    references: Vec<Occurrence>,
+}
+
+/// Write an LSIF index: a document per file, a range and document symbol
+/// per definition tag, a range per indexed reference, and name-based
+/// definition and reference results linking them. Document URIs are
+/// absolute, after `--path-map` rewriting.
+pub fn write_lsif(
+    out: &mut dyn Write,
+    all_tags: &[(PathBuf, Vec<Tag>)],
+    all_indexes: &[(PathBuf, VerusIndex)],
+    style: &PathStyle,
+) -> Result<()> {
+    let uri_style = PathStyle {
+        absolute: true,
+        ..style.clone()
+    };
+    let indexes: BTreeMap<&Path, &VerusIndex> =
+        all_indexes.iter().map(|(path, index)| (path.as_path(), index)).collect();
+    let mut lsif = Emitter { out, next_id: 1 };
+
+    let root = uri_style.source_name(&path_names::absolute(Path::new(".")), Path::new("-"));
+    lsif.vertex(
+        "metaData",
+        json!({
+            "version": "0.4.3",
+            "projectRoot": file_uri(&root),
+            "positionEncoding": "utf-16",
+            "toolInfo": {"name": "verus-etags", "version": env!("CARGO_PKG_VERSION")},
+        }),
+    )?;
+    let project = lsif.vertex("project", json!({"kind": "rust"}))?;
+
+    // Only names defined somewhere in the project can be linked
+    let defined: BTreeSet<&str> = all_tags.iter().flat_map(|(_, tags)| tags).filter_map(Tag::identifier).collect();
+    let mut symbols: BTreeMap<&str, Symbol> = BTreeMap::new();
+    let mut documents = Vec::new();
+    for (file_path, tags) in all_tags {
+        let uri = file_uri(&uri_style.source_name(file_path, Path::new("-")));
+        let document = lsif.vertex("document", json!({"uri": uri, "languageId": "rust"}))?;
+        documents.push(document);
+        let lines = WideLines::new(&fs::read_to_string(file_path).unwrap_or_default());
+
+        let mut ranges = Vec::new();
+        let mut outline = Vec::new();
+        for tag in tags {
+            let Some(identifier) = tag.identifier() else {
+                continue;
+            };
+            let start = lines.position(tag.line, tag.column);
+            let range = lsif.vertex(
+                "range",
+                json!({
+                    "start": start,
+                    "end": lines.position(tag.line, tag.column + identifier.chars().count()),
+                    "tag": {
+                        "type": "definition",
+                        "text": identifier,
+                        "kind": tag.kind.lsp_symbol_kind(),
+                        "fullRange": {"start": lines.position(tag.start_line, 1), "end": lines.position(tag.end_line + 1, 1)},
+                    },
+                }),
+            )?;
+            ranges.push(range);
+            outline.push((tag.start_byte, tag.end_byte, range));
+            symbols.entry(identifier).or_default().definitions.push(Occurrence { document, range });
+        }
+
+        let references = indexes.get(file_path.as_path()).map(|index| index.references.as_slice()).unwrap_or_default();
+        for reference in references.iter().filter(|reference| defined.contains(reference.name.as_str())) {
+            let range = lsif.vertex(
+                "range",
+                json!({
+                    "start": lines.position(reference.line, reference.column),
+                    "end": lines.position(reference.line, reference.column + reference.name.chars().count()),
+                    "tag": {"type": "reference", "text": reference.name},
+                }),
+            )?;
+            ranges.push(range);
+            symbols.entry(&reference.name).or_default().references.push(Occurrence { document, range });
+        }
+
+        if !ranges.is_empty() {
+            lsif.edge_many("contains", document, &ranges, json!({}))?;
+        }
+        let symbol_result = lsif.vertex("documentSymbolResult", json!({"result": document_symbols(outline)}))?;
+        lsif.edge("textDocument/documentSymbol", document, symbol_result)?;
+    }
+    if !documents.is_empty() {
+        lsif.edge_many("contains", project, &documents, json!({}))?;
+    }
+
+    for symbol in symbols.values() {
+        let result_set = lsif.vertex("resultSet", json!({}))?;
+        for occurrence in symbol.definitions.iter().chain(&symbol.references) {
+            lsif.edge("next", occurrence.range, result_set)?;
+        }
+
+        let definition_result = lsif.vertex("definitionResult", json!({}))?;
+        lsif.edge("textDocument/definition", result_set, definition_result)?;
+        for (document, ranges) in by_document(&symbol.definitions) {
+            lsif.edge_many("item", definition_result, &ranges, json!({"document": document}))?;
+        }
+
+        let reference_result = lsif.vertex("referenceResult", json!({}))?;
+        lsif.edge("textDocument/references", result_set, reference_result)?;
+        for (property, occurrences) in [("definitions", &symbol.definitions), ("references", &symbol.references)] {
+            for (document, ranges) in by_document(occurrences) {
+                let extra = json!({"document": document, "property": property});
+                lsif.edge_many("item", reference_result, &ranges, extra)?;
+            }
+        }
+    }
+
+    lsif.out.flush()?;
+    Ok(())
+}
+
+/// LSIF document symbols for `(start_byte, end_byte, range)` definitions
+fn document_symbols(outline: Vec<(usize, usize, u64)>) -> Vec<Value> {
+    fn symbol(node: &Node<u64>) -> Value {
+        if node.children.is_empty() {
+            json!({"id": node.item})
+        } else {
+            json!({"id": node.item, "children": node.children.iter().map(symbol).collect::<Vec<_>>()})
+        }
+    }
+    tag_tree::nest(outline).iter().map(symbol).collect()
+}
+
+fn by_document(occurrences: &[Occurrence]) -> BTreeMap<u64, Vec<u64>> {
+    let mut grouped: BTreeMap<u64, Vec<u64>> = BTreeMap::new();
+    for occurrence in occurrences {
+        grouped.entry(occurrence.document).or_default().push(occurrence.range);
+    }
+    grouped
+}
+
+/// The lines of a text that hold characters outside ASCII, by 1-based line
+/// number. Tags count columns in characters and LSP in UTF-16 code units,
+/// which only differ on these lines.
+pub struct WideLines(BTreeMap<usize, String>);
+
+impl WideLines {
+    pub fn new(text: &str) -> Self {
+        let lines = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.is_ascii())
+            .map(|(index, line)| (index + 1, line.to_string()))
+            .collect();
+        WideLines(lines)
+    }
+
+    /// Zero-based LSP position of a 1-based line and character column
+    pub fn position(&self, line: usize, column: usize) -> Value {
+        let character = match self.0.get(&line) {
+            Some(text) => text.chars().take(column.saturating_sub(1)).map(char::len_utf16).sum(),
+            None => column.saturating_sub(1),
+        };
+        json!({"line": line.saturating_sub(1), "character": character})
+    }
+
+    /// Zero-based character column of an LSP `character` on a 1-based line
+    pub fn column(&self, line: usize, character: usize) -> usize {
+        let Some(text) = self.0.get(&line) else {
+            return character;
+        };
+        let mut units = 0;
+        text.chars()
+            .take_while(|c| {
+                units += c.len_utf16();
+                units <= character
+            })
+            .count()
+    }
+}
+
+/// `file://` URI of an absolute path, percent-encoding everything but
+/// unreserved characters, `/` and `:`
+pub fn file_uri(path: &Path) -> String {
+    let mut uri = String::from("file://");
+    let text = path.to_string_lossy().replace('\\', "/");
+    // Windows paths start with a drive letter instead of `/`
+    if !text.starts_with('/') {
+        uri.push('/');
+    }
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => uri.push(byte as char),
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+/// Writes LSIF vertices and edges as JSON Lines with consecutive ids
+struct Emitter<'a> {
+    out: &'a mut dyn Write,
+    next_id: u64,
+}
+
+impl Emitter<'_> {
+    fn vertex(&mut self, label: &str, fields: Value) -> Result<u64> {
+        self.emit("vertex", label, fields)
+    }
+
+    fn edge(&mut self, label: &str, out_v: u64, in_v: u64) -> Result<u64> {
+        self.emit("edge", label, json!({"outV": out_v, "inV": in_v}))
+    }
+
+    /// An edge to several vertices (`contains` and `item`), with extra `fields`
+    fn edge_many(&mut self, label: &str, out_v: u64, in_vs: &[u64], mut fields: Value) -> Result<u64> {
+        fields["outV"] = json!(out_v);
+        fields["inVs"] = json!(in_vs);
+        self.emit("edge", label, fields)
+    }
+
+    fn emit(&mut self, kind: &str, label: &str, fields: Value) -> Result<u64> {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let mut record = Map::new();
+        record.insert("id".to_string(), json!(id));
+        record.insert("type".to_string(), json!(kind));
+        record.insert("label".to_string(), json!(label));
+        if let Value::Object(fields) = fields {
+            record.extend(fields);
+        }
+        writeln!(self.out, "{}", Value::Object(record))?;
+        Ok(id)
+    }
+}
@@ -0,0 +1,429 @@
+use anyhow::{bail, Context, Result};
+use proc_macro2::{TokenStream, TokenTree};
+use serde_json::{json, Value};
+use std::borrow::Cow;
+use std::collections::btree_map::Entry;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use crate::lsif::{file_uri, WideLines};
+use crate::path_names;
+use crate::tag_tree::{self, Node};
+use crate::tag_visitor::Tag;
+use crate::verus_index::VerusIndex;
+
+/// JSON-RPC error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// Tags and index of one file
+struct FileIndex {
+    tags: Vec<Tag>,
+    index: VerusIndex,
+    lines: WideLines,
+}
+
+/// The identifier tokens of a document, lexed once per version of its text
+struct Identifiers {
+    /// `(line, start column, end column, name)`, lines 1-based and columns
+    /// 0-based in characters, in source order
+    tokens: Vec<(usize, usize, usize, String)>,
+    lines: WideLines,
+}
+
+/// The workspace index, the text of the documents open in the editor, and
+/// the identifiers of the documents requests have pointed into
+struct Server<F> {
+    files: BTreeMap<PathBuf, FileIndex>,
+    buffers: BTreeMap<PathBuf, String>,
+    lexed: BTreeMap<PathBuf, Identifiers>,
+    tag: F,
+}
+
+/// Serve the Language Server Protocol on stdin/stdout until `exit`.
+/// `indexed` is the workspace tagged at startup; `tag` re-tags the text of
+/// a file when it is opened, changed or saved, and returns `None` if
+/// nothing in it could be tagged, which keeps the previous tags.
+pub fn serve<F>(indexed: Vec<(PathBuf, Vec<Tag>, VerusIndex)>, tag: F) -> Result<()>
+where
+    F: Fn(&Path, &str) -> Option<(Vec<Tag>, VerusIndex)>,
+{
+    let files = indexed
+        .into_iter()
+        .map(|(path, tags, index)| {
+            let lines = WideLines::new(&fs::read_to_string(&path).unwrap_or_default());
+            (path_names::absolute(&path), FileIndex { tags, index, lines })
+        })
+        .collect();
+    let mut server = Server {
+        files,
+        buffers: BTreeMap::new(),
+        lexed: BTreeMap::new(),
+        tag,
+    };
+
+    let mut input = io::stdin().lock();
+    let mut output = io::stdout().lock();
+    let mut shutdown = false;
+    while let Some(body) = read_message(&mut input)? {
+        let message: Value = match serde_json::from_slice(&body) {
+            Ok(message) => message,
+            Err(error) => {
+                // Without a readable id, the error answers no request in particular
+                let error = json!({"code": PARSE_ERROR, "message": format!("Invalid JSON: {}", error)});
+                write_message(&mut output, &json!({"jsonrpc": "2.0", "id": null, "error": error}))?;
+                continue;
+            }
+        };
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+        let Some(id) = message.get("id") else {
+            match method {
+                "exit" if shutdown => return Ok(()),
+                "exit" => bail!("LSP client exited without shutting down"),
+                _ => server.notify(method, params),
+            }
+            continue;
+        };
+        // Responses to requests the server never sent
+        if method.is_empty() {
+            continue;
+        }
+
+        let result = match method {
+            _ if shutdown => Err((INVALID_REQUEST, "Server is shutting down".to_string())),
+            "initialize" => Ok(capabilities()),
+            "shutdown" => {
+                shutdown = true;
+                Ok(Value::Null)
+            }
+            _ => server.request(method, params),
+        };
+        let response = match result {
+            Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+            Err((code, message)) => {
+                json!({"jsonrpc": "2.0", "id": id, "error": {"code": code, "message": message}})
+            }
+        };
+        write_message(&mut output, &response)?;
+    }
+    Ok(())
+}
+
+fn capabilities() -> Value {
+    json!({
+        "capabilities": {
+            // Columns are converted from characters to UTF-16, the LSP default
+            "positionEncoding": "utf-16",
+            // Full text on every change, and on save if the client has it
+            "textDocumentSync": {"openClose": true, "change": 1, "save": {"includeText": true}},
+            "workspaceSymbolProvider": true,
+            "documentSymbolProvider": true,
+            "definitionProvider": true,
+            "referencesProvider": true,
+        },
+        "serverInfo": {"name": "verus-etags", "version": env!("CARGO_PKG_VERSION")},
+    })
+}
+
+impl<F> Server<F>
+where
+    F: Fn(&Path, &str) -> Option<(Vec<Tag>, VerusIndex)>,
+{
+    fn notify(&mut self, method: &str, params: &Value) {
+        let Some(path) = document_path(params) else {
+            return;
+        };
+        match method {
+            "textDocument/didOpen" => {
+                if let Some(text) = params["textDocument"]["text"].as_str() {
+                    self.update(path, text.to_string());
+                }
+            }
+            "textDocument/didChange" => {
+                let changes = params["contentChanges"].as_array();
+                if let Some(text) = changes.and_then(|changes| changes.last()?["text"].as_str()) {
+                    self.update(path, text.to_string());
+                }
+            }
+            "textDocument/didSave" => match params["text"].as_str() {
+                Some(text) => self.update(path, text.to_string()),
+                None => {
+                    if let Ok(text) = fs::read_to_string(&path) {
+                        self.update(path, text);
+                    }
+                }
+            },
+            "textDocument/didClose" => {
+                self.buffers.remove(&path);
+                self.lexed.remove(&path);
+                // Unsaved changes are gone, so go back to the file on disk
+                match fs::read_to_string(&path) {
+                    Ok(text) => self.retag(&path, &text),
+                    Err(_) => {
+                        self.files.remove(&path);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn update(&mut self, path: PathBuf, text: String) {
+        self.retag(&path, &text);
+        self.lexed.remove(&path);
+        self.buffers.insert(path, text);
+    }
+
+    fn retag(&mut self, path: &Path, text: &str) {
+        if let Some((tags, index)) = (self.tag)(path, text) {
+            let lines = WideLines::new(text);
+            self.files.insert(path.to_path_buf(), FileIndex { tags, index, lines });
+        }
+    }
+
+    fn request(&mut self, method: &str, params: &Value) -> Result<Value, (i64, String)> {
+        match method {
+            "workspace/symbol" => Ok(self.workspace_symbols(params["query"].as_str().unwrap_or_default())),
+            "textDocument/documentSymbol" => {
+                let path = document_path(params).ok_or_else(invalid_document)?;
+                Ok(self.document_symbols(&path))
+            }
+            "textDocument/definition" => {
+                let path = document_path(params).ok_or_else(invalid_document)?;
+                Ok(match self.identifier_at(&path, &params["position"]) {
+                    Some(name) => Value::Array(self.definitions(&name)),
+                    None => Value::Null,
+                })
+            }
+            "textDocument/references" => {
+                let path = document_path(params).ok_or_else(invalid_document)?;
+                let Some(name) = self.identifier_at(&path, &params["position"]) else {
+                    return Ok(Value::Null);
+                };
+                let mut locations = Vec::new();
+                if params["context"]["includeDeclaration"].as_bool().unwrap_or(false) {
+                    locations.extend(self.definitions(&name));
+                }
+                locations.extend(self.references(&name));
+                Ok(Value::Array(locations))
+            }
+            _ => Err((METHOD_NOT_FOUND, format!("Unsupported method: {}", method))),
+        }
+    }
+
+    /// Tags whose name contains `query`, ignoring case
+    fn workspace_symbols(&self, query: &str) -> Value {
+        let query = query.to_lowercase();
+        let symbols = self
+            .files
+            .iter()
+            .flat_map(|(path, file)| file.tags.iter().map(move |tag| (path, file, tag)))
+            .filter(|(_, _, tag)| tag.name.to_lowercase().contains(&query))
+            .map(|(path, file, tag)| {
+                let mut symbol = json!({
+                    "name": tag.name,
+                    "kind": tag.kind.lsp_symbol_kind(),
+                    "location": location(path, file, tag),
+                });
+                if let Some(scope) = &tag.scope {
+                    symbol["containerName"] = json!(scope);
+                }
+                symbol
+            })
+            .collect();
+        Value::Array(symbols)
+    }
+
+    /// The tags of a file, nested by their item ranges
+    fn document_symbols(&self, path: &Path) -> Value {
+        fn symbol(file: &FileIndex, node: &Node<&Tag>) -> Value {
+            let tag = node.item;
+            let mut symbol = json!({
+                "name": tag.name,
+                "kind": tag.kind.lsp_symbol_kind(),
+                "range": {"start": file.lines.position(tag.start_line, 1), "end": file.lines.position(tag.end_line + 1, 1)},
+                "selectionRange": selection_range(file, tag),
+                "children": node.children.iter().map(|child| symbol(file, child)).collect::<Vec<_>>(),
+            });
+            if let Some(mode) = tag.mode {
+                symbol["detail"] = json!(mode);
+            }
+            symbol
+        }
+
+        let Some(file) = self.files.get(path) else {
+            return Value::Null;
+        };
+        let outline = file.tags.iter().map(|tag| (tag.start_byte, tag.end_byte, tag)).collect();
+        Value::Array(tag_tree::nest(outline).iter().map(|node| symbol(file, node)).collect())
+    }
+
+    /// Locations of the tags defining `name`
+    fn definitions(&self, name: &str) -> Vec<Value> {
+        self.files
+            .iter()
+            .flat_map(|(path, file)| file.tags.iter().map(move |tag| (path, file, tag)))
+            .filter(|(_, _, tag)| tag.identifier() == Some(name))
+            .map(|(path, file, tag)| location(path, file, tag))
+            .collect()
+    }
+
+    /// Locations of the indexed references to `name`
+    fn references(&self, name: &str) -> Vec<Value> {
+        self.files
+            .iter()
+            .flat_map(|(path, file)| file.index.references.iter().map(move |reference| (path, file, reference)))
+            .filter(|(_, _, reference)| reference.name == name)
+            .map(|(path, file, reference)| {
+                let end = reference.column + reference.name.chars().count();
+                json!({
+                    "uri": file_uri(path),
+                    "range": {
+                        "start": file.lines.position(reference.line, reference.column),
+                        "end": file.lines.position(reference.line, end),
+                    },
+                })
+            })
+            .collect()
+    }
+
+    /// The identifier token at an LSP position, in the open buffer or the
+    /// file on disk, including the position just after it. `None` if there
+    /// is none, or the text does not lex.
+    fn identifier_at(&mut self, path: &Path, at: &Value) -> Option<String> {
+        let identifiers = match self.lexed.entry(path.to_path_buf()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let text = match self.buffers.get(path) {
+                    Some(text) => Cow::Borrowed(text),
+                    None => Cow::Owned(fs::read_to_string(path).ok()?),
+                };
+                entry.insert(Identifiers::lex(&text))
+            }
+        };
+        let line = at["line"].as_u64()? as usize + 1;
+        let column = identifiers.lines.column(line, at["character"].as_u64()? as usize);
+        identifiers
+            .tokens
+            .iter()
+            .find(|(start_line, start, end, _)| *start_line == line && *start <= column && column <= *end)
+            .map(|(_, _, _, name)| name.clone())
+    }
+}
+
+impl Identifiers {
+    /// The identifiers of `text`; none if it does not lex
+    fn lex(text: &str) -> Self {
+        let mut tokens = Vec::new();
+        if let Ok(stream) = text.parse::<TokenStream>() {
+            collect_identifiers(stream, &mut tokens);
+        }
+        Identifiers {
+            tokens,
+            lines: WideLines::new(text),
+        }
+    }
+}
+
+fn collect_identifiers(stream: TokenStream, tokens: &mut Vec<(usize, usize, usize, String)>) {
+    for token in stream {
+        match token {
+            TokenTree::Ident(ident) => {
+                let (start, end) = (ident.span().start(), ident.span().end());
+                tokens.push((start.line, start.column, end.column, ident.to_string()));
+            }
+            TokenTree::Group(group) => collect_identifiers(group.stream(), tokens),
+            _ => {}
+        }
+    }
+}
+
+fn location(path: &Path, file: &FileIndex, tag: &Tag) -> Value {
+    json!({"uri": file_uri(path), "range": selection_range(file, tag)})
+}
+
+/// The defined identifier, or the start of the tag if it defines none
+fn selection_range(file: &FileIndex, tag: &Tag) -> Value {
+    let length = tag.identifier().map_or(0, |identifier| identifier.chars().count());
+    json!({
+        "start": file.lines.position(tag.line, tag.column),
+        "end": file.lines.position(tag.line, tag.column + length),
+    })
+}
+
+fn invalid_document() -> (i64, String) {
+    (INVALID_PARAMS, "Missing or unsupported textDocument.uri".to_string())
+}
+
+/// The absolute path of the `textDocument` of a request or notification
+fn document_path(params: &Value) -> Option<PathBuf> {
+    uri_path(params["textDocument"]["uri"].as_str()?)
+}
+
+/// Path of a `file://` URI, decoding percent escapes. `None` for other schemes.
+fn uri_path(uri: &str) -> Option<PathBuf> {
+    let encoded = uri.strip_prefix("file://")?;
+    let mut bytes = Vec::with_capacity(encoded.len());
+    let mut rest = encoded.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        let escaped = (byte == b'%')
+            .then(|| tail.get(..2))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match escaped {
+            Some(decoded) => {
+                bytes.push(decoded);
+                rest = &tail[2..];
+            }
+            None => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+    let mut path = String::from_utf8(bytes).ok()?;
+    // `file:///C:/...` on Windows
+    if path.as_bytes().get(2) == Some(&b':') && path.starts_with('/') {
+        path.remove(0);
+    }
+    Some(path_names::absolute(Path::new(&path)))
+}
+
+/// Read the body of one `Content-Length`-framed message. `None` at end of input.
+fn read_message(input: &mut impl BufRead) -> Result<Option<Vec<u8>>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header).context("Failed to read LSP header")? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = Some(value.trim().parse::<usize>().context("Invalid Content-Length")?);
+            }
+        }
+    }
+
+    let Some(length) = length else {
+        bail!("LSP message without Content-Length");
+    };
+    let mut body = vec![0; length];
+    input.read_exact(&mut body).context("Failed to read LSP message")?;
+    Ok(Some(body))
+}
+
+fn write_message(output: &mut impl Write, message: &Value) -> Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()?;
+    Ok(())
+}
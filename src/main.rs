@@ -1,50 +1,214 @@
-use anyhow::{Context, Result};
-use clap::Parser;
+use anyhow::{bail, Context, Result};
+use clap::{Parser, Subcommand};
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
-use std::io::Write;
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
-use walkdir::WalkDir;
+use ignore::overrides::{Override, OverrideBuilder};
+use ignore::WalkBuilder;
 
+mod cargo_deps;
+mod cargo_discovery;
+mod diagnostics;
+mod lsif;
+mod lsp;
+mod path_names;
+mod recovery;
+mod sqlite_db;
+mod stats;
+mod syntax_generations;
+mod sysroot;
+mod tag_tree;
 mod tag_visitor;
+mod verus_index;
+mod which;
 use tag_visitor::{Tag, TagVisitor};
+use diagnostics::{ErrorFormat, Failure};
+use recovery::VerusBodies;
+use path_names::PathStyle;
+use verus_index::VerusIndex;
 
 #[derive(Parser, Debug)]
 #[command(name = "verus-etags")]
 #[command(version)]
 #[command(about = "Generate etags for Verus/Rust source files", long_about = None)]
 #[command(disable_version_flag = true)]
+#[command(subcommand_negates_reqs = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Print version
     #[arg(short = 'v', long = "version")]
     version: bool,
 
-    /// Input files or directories to process
-    #[arg(required_unless_present = "version")]
+    /// Input files or directories to process ("-" reads source from stdin)
+    #[arg(required_unless_present_any = ["version", "manifest_path", "file_list"])]
     paths: Vec<PathBuf>,
 
-    /// Output file (default: TAGS)
+    /// Read input file names from FILE, one per line ("-" for stdin)
+    #[arg(short = 'L', value_name = "FILE")]
+    file_list: Option<PathBuf>,
+
+    /// File names read with -L are separated by NUL instead of newlines
+    #[arg(long, requires = "file_list")]
+    null: bool,
+
+    /// File name recorded for source read from stdin ("-")
+    #[arg(long, value_name = "PATH")]
+    stdin_filename: Option<PathBuf>,
+
+    /// Index the crates of a Cargo manifest (workspace members, local path
+    /// dependencies and every target), following `mod` declarations
+    #[arg(long, value_name = "Cargo.toml")]
+    manifest_path: Option<PathBuf>,
+
+    /// Tag the dependencies locked in Cargo.lock (from vendor/ or Cargo's local
+    /// registry cache) into FILE, and include FILE from the main tags file
+    #[arg(long, value_name = "FILE", requires = "manifest_path")]
+    deps_tags: Option<PathBuf>,
+
+    /// Reference another tags file through an etags include section (repeatable)
+    #[arg(long, value_name = "PATH")]
+    include_tags: Vec<PathBuf>,
+
+    /// Write one tags file per crate (or top-level directory) next to its
+    /// sources, and a root tags file that includes them all
+    #[arg(long)]
+    split_per_crate: bool,
+
+    /// Tag core, alloc and std from the active toolchain's rust-src (cached
+    /// per toolchain) and include them from the main tags file
+    #[arg(long)]
+    sysroot_std: bool,
+
+    /// Format of the output file
+    #[arg(long, value_enum, default_value = "etags")]
+    format: OutputFormat,
+
+    /// Output file (default: TAGS, "-" for stdout)
     #[arg(short = 'o', long, visible_alias = "file", short_alias = 'f', default_value = "TAGS")]
     output: PathBuf,
 
+    /// Write file names relative to the directory of the tags file
+    #[arg(long, conflicts_with = "absolute")]
+    tag_relative: bool,
+
+    /// Write absolute file names
+    #[arg(long)]
+    absolute: bool,
+
+    /// Rewrite file names starting with FROM to start with TO (repeatable)
+    #[arg(long, value_name = "FROM=TO", value_parser = path_names::parse_path_map)]
+    path_map: Vec<(PathBuf, PathBuf)>,
+
     /// Append to existing tags file instead of overwriting
     #[arg(short, long)]
     append: bool,
 
     /// Recurse into directories (default: true, use --no-recurse to disable)
-    #[arg(short = 'R', long, default_value_t = true, action = clap::ArgAction::SetTrue)]
+    #[arg(short = 'R', long, global = true, default_value_t = true, action = clap::ArgAction::SetTrue)]
     recurse: bool,
     
     /// Do not recurse into subdirectories
-    #[arg(long, conflicts_with = "recurse")]
+    #[arg(long, global = true, conflicts_with = "recurse")]
     no_recurse: bool,
 
+    /// Do not honor .gitignore, .ignore or git's global excludes, and do not skip target/
+    #[arg(long, global = true)]
+    no_ignore: bool,
+
+    /// Only process files matching this glob (repeatable)
+    #[arg(long, global = true, value_name = "GLOB")]
+    include: Vec<String>,
+
+    /// Skip files and directories matching this glob (repeatable)
+    #[arg(long, global = true, value_name = "GLOB")]
+    exclude: Vec<String>,
+
+    /// Exit with an error if any file could not be parsed
+    #[arg(long, global = true)]
+    strict: bool,
+
+    /// Format of the skipped-files summary printed to stderr
+    #[arg(long, global = true, value_enum, default_value = "human")]
+    error_format: ErrorFormat,
+
     /// Verbose output
-    #[arg(short = 'V', long, visible_alias = "verbose")]
+    #[arg(short = 'V', long, global = true, visible_alias = "verbose")]
     verbose_mode: bool,
 
     /// Sort tags (0=unsorted, 1=sorted, 2=foldcase)
     #[arg(short, long, value_name = "0|1|2", default_value = "1")]
     sort: u8,
+
+    /// Write broadcast group membership and `broadcast use` sites as JSON
+    #[arg(long, value_name = "FILE")]
+    broadcast_json: Option<PathBuf>,
+
+    /// Add `trigger:fn_name` tags for quantifier trigger terms
+    #[arg(long)]
+    trigger_tags: bool,
+
+    /// Write the quantifier trigger index as JSON
+    #[arg(long, value_name = "FILE")]
+    trigger_json: Option<PathBuf>,
+
+    /// Write the caller/callee graph in Graphviz DOT format
+    #[arg(long, value_name = "FILE")]
+    call_graph_dot: Option<PathBuf>,
+
+    /// Write the caller/callee graph as JSON
+    #[arg(long, value_name = "FILE")]
+    call_graph_json: Option<PathBuf>,
+
+    /// Write a report of proof and spec fns that are never referenced
+    #[arg(long, value_name = "FILE")]
+    unused_report: Option<PathBuf>,
+}
+
+/// What the output file contains
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum OutputFormat {
+    /// Emacs etags
+    Etags,
+    /// JSON Lines, one object per tag
+    Json,
+    /// SQLite database with tags, files and references tables
+    Sqlite,
+    /// LSIF code-intelligence index
+    Lsif,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Report spec/proof/exec function and line counts per file and module
+    Stats {
+        /// Input files or directories to process
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+
+        /// Write CSV instead of a table
+        #[arg(long)]
+        csv: bool,
+    },
+    /// Print the tags of a file as a JSON tree nested by containment
+    Outline {
+        /// Source file, or `-` for stdin
+        file: PathBuf,
+    },
+    /// Print the chain of items enclosing FILE:LINE positions
+    Which {
+        /// Positions as FILE:LINE or FILE:LINE:COLUMN; with none, or `-`,
+        /// they are picked out of the lines of stdin, e.g. a verifier log
+        positions: Vec<String>,
+    },
+    /// Serve symbols, definitions and references over LSP on stdin/stdout
+    Lsp {
+        /// Workspace files or directories to index at startup
+        #[arg(default_value = ".")]
+        paths: Vec<PathBuf>,
+    },
 }
 
 fn main() -> Result<()> {
@@ -56,117 +220,321 @@ fn main() -> Result<()> {
         return Ok(());
     }
 
+    if let Some(Command::Stats { paths, csv }) = &args.command {
+        return run_stats(paths, *csv, &args);
+    }
+    if let Some(Command::Outline { file }) = &args.command {
+        return run_outline(file, &args);
+    }
+    if let Some(Command::Which { positions }) = &args.command {
+        return run_which(positions, &args);
+    }
+    if let Some(Command::Lsp { paths }) = &args.command {
+        return run_lsp(paths, &args);
+    }
+
+    let stdin = Path::new("-");
+    if args.file_list.as_deref() == Some(stdin) && args.paths.iter().any(|path| path == stdin) {
+        bail!("Cannot read both the file list (-L -) and source (-) from stdin");
+    }
+    if args.split_per_crate && args.output == stdin {
+        bail!("--split-per-crate cannot write to stdout");
+    }
+    let links_tags_files =
+        args.split_per_crate || !args.include_tags.is_empty() || args.deps_tags.is_some() || args.sysroot_std;
+    if args.format != OutputFormat::Etags && links_tags_files {
+        bail!("--split-per-crate, --include-tags, --deps-tags and --sysroot-std need --format etags");
+    }
+
     let mut all_tags: Vec<(PathBuf, Vec<Tag>)> = Vec::new();
+    let mut all_indexes: Vec<(PathBuf, VerusIndex)> = Vec::new();
+    let mut skipped: Vec<(PathBuf, Failure)> = Vec::new();
 
-    // If append mode, load existing tags
-    if args.append && args.output.exists() {
+    // If append mode, load existing tags (a SQLite database is updated in place instead)
+    if args.append && args.format != OutputFormat::Sqlite && args.output.exists() {
         if args.verbose_mode {
             eprintln!("Appending to existing tags file: {}", args.output.display());
         }
         all_tags = read_existing_tags(&args.output)?;
     }
 
-    // Determine if we should recurse
-    let should_recurse = args.recurse && !args.no_recurse;
-
-    // Collect all Rust files
-    for path in &args.paths {
-        if path.is_file() {
-            if is_rust_file(path) {
-                if args.verbose_mode {
-                    eprintln!("Processing file: {}", path.display());
-                }
-                match process_file(path) {
-                    Ok(tags) => all_tags.push((path.clone(), tags)),
-                    Err(e) => {
-                        if args.verbose_mode {
-                            eprintln!("Warning: Skipping file {}: {}", path.display(), e);
-                        }
-                    }
-                }
-            }
-        } else if path.is_dir() {
-            if should_recurse {
-                // Recursive directory traversal
-                for entry in WalkDir::new(path)
-                    .follow_links(true)
-                    .into_iter()
-                    .filter_entry(|e| {
-                        // Skip hidden directories (but not the root ".")
-                        // Skip Emacs temp files
-                        if let Some(name) = e.file_name().to_str() {
-                            // Don't filter the current directory "."
-                            if name == "." {
-                                return true;
-                            }
-                            // Filter hidden dirs/files and Emacs temp files
-                            if name.starts_with(".#") || name.ends_with('~') || 
-                               (name.starts_with('#') && name.ends_with('#')) || 
-                               name.starts_with('.') {
-                                return false;
-                            }
-                        }
-                        true
-                    })
-                {
-                    let entry = match entry {
-                        Ok(e) => e,
-                        Err(_) => continue, // Skip entries that can't be read (broken symlinks, etc.)
-                    };
-                    let file_path = entry.path();
-                    if file_path.is_file() && is_rust_file(file_path) {
-                        if args.verbose_mode {
-                            eprintln!("Processing file: {}", file_path.display());
-                        }
-                        match process_file(file_path) {
-                            Ok(tags) => all_tags.push((file_path.to_path_buf(), tags)),
-                            Err(e) => {
-                                if args.verbose_mode {
-                                    eprintln!("Warning: Skipping file {}: {}", file_path.display(), e);
-                                }
-                            }
-                        }
-                    }
-                }
-            } else {
-                // Non-recursive: only process files in the immediate directory
-                if let Ok(entries) = fs::read_dir(path) {
-                    for entry in entries.flatten() {
-                        let file_path = entry.path();
-                        if file_path.is_file() && is_rust_file(&file_path) {
-                            if args.verbose_mode {
-                                eprintln!("Processing file: {}", file_path.display());
-                            }
-                            match process_file(&file_path) {
-                                Ok(tags) => all_tags.push((file_path, tags)),
-                                Err(e) => {
-                                    if args.verbose_mode {
-                                        eprintln!("Warning: Skipping file {}: {}", file_path.display(), e);
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
+    if let Some(manifest_path) = &args.manifest_path {
+        for file_path in cargo_discovery::crate_files(manifest_path)? {
+            add_file(&file_path, &args, &mut all_tags, &mut all_indexes, &mut skipped);
         }
     }
 
+    let listed = match &args.file_list {
+        Some(list_path) => read_file_list(list_path, args.null)?,
+        None => Vec::new(),
+    };
+    process_paths(&listed, &args, &mut all_tags, &mut all_indexes, &mut skipped)?;
+
+    process_paths(&args.paths, &args, &mut all_tags, &mut all_indexes, &mut skipped)?;
+
+    // Same input, same bytes: file sections in a fixed order and each file
+    // once, whatever order the file system or the arguments produced them in
+    sort_sections(&mut all_tags);
+    sort_sections(&mut all_indexes);
+    sort_sections(&mut skipped);
+
     // Sort tags if requested
     if args.sort > 0 {
         sort_tags(&mut all_tags, args.sort == 2);
     }
 
-    // Write etags format
-    write_etags(&args.output, &all_tags, args.append)?;
+    let style = PathStyle {
+        tag_relative: args.tag_relative,
+        absolute: args.absolute,
+        path_map: args.path_map.clone(),
+    };
+
+    let mut includes = args.include_tags.clone();
+    if let (Some(deps_path), Some(manifest_path)) = (&args.deps_tags, &args.manifest_path) {
+        write_dependency_tags(deps_path, manifest_path, &style, &args)?;
+        includes.push(deps_path.clone());
+    }
+    if args.sysroot_std {
+        includes.push(std_tags(&args)?);
+    }
+
+    // Write the tags file
+    match args.format {
+        OutputFormat::Json => write_json_tags(&args.output, &all_tags, &style)?,
+        OutputFormat::Sqlite => {
+            let removed = untagged_files(listed.iter().chain(&args.paths), &all_tags, &skipped);
+            sqlite_db::write_sqlite(&args.output, &all_tags, &all_indexes, &removed, &style, args.append)?
+        }
+        OutputFormat::Lsif => lsif::write_lsif(&mut create_output(&args.output)?, &all_tags, &all_indexes, &style)?,
+        OutputFormat::Etags if args.split_per_crate => {
+            let root_tags = write_split_tags(&args.output, &all_tags, &style, &mut includes)?;
+            write_etags(&args.output, &root_tags, &includes, &style, args.append)?;
+        }
+        OutputFormat::Etags => write_etags(&args.output, &all_tags, &includes, &style, args.append)?,
+    }
+
+    if let Some(json_path) = &args.broadcast_json {
+        let report = verus_index::broadcast_json(&all_indexes);
+        fs::write(json_path, serde_json::to_string_pretty(&report)?)
+            .with_context(|| format!("Failed to write broadcast JSON: {}", json_path.display()))?;
+    }
+
+    if let Some(json_path) = &args.trigger_json {
+        let report = verus_index::trigger_json(&all_indexes);
+        fs::write(json_path, serde_json::to_string_pretty(&report)?)
+            .with_context(|| format!("Failed to write trigger JSON: {}", json_path.display()))?;
+    }
+
+    if let Some(dot_path) = &args.call_graph_dot {
+        fs::write(dot_path, verus_index::call_graph_dot(&all_indexes))
+            .with_context(|| format!("Failed to write call graph: {}", dot_path.display()))?;
+    }
+
+    if let Some(json_path) = &args.call_graph_json {
+        let report = verus_index::call_graph_json(&all_indexes);
+        fs::write(json_path, serde_json::to_string_pretty(&report)?)
+            .with_context(|| format!("Failed to write call graph: {}", json_path.display()))?;
+    }
+
+    if let Some(report_path) = &args.unused_report {
+        write_unused_report(report_path, &all_indexes)?;
+    }
 
     if args.verbose_mode {
         eprintln!("Generated {} with {} files", args.output.display(), all_tags.len());
     }
 
+    finish(&skipped, &args)
+}
+
+/// Summarize skipped files and, with --strict, fail if there were any
+fn finish(skipped: &[(PathBuf, Failure)], args: &Args) -> Result<()> {
+    diagnostics::report_skipped(skipped, args.error_format);
+    if args.strict && !skipped.is_empty() {
+        bail!("{} file(s) could not be parsed", skipped.len());
+    }
     Ok(())
 }
 
+fn run_stats(paths: &[PathBuf], csv: bool, args: &Args) -> Result<()> {
+    let mut all_tags: Vec<(PathBuf, Vec<Tag>)> = Vec::new();
+    let mut all_indexes: Vec<(PathBuf, VerusIndex)> = Vec::new();
+    let mut skipped: Vec<(PathBuf, Failure)> = Vec::new();
+    process_paths(paths, args, &mut all_tags, &mut all_indexes, &mut skipped)?;
+    // Rows in path order, and each file once, as in TAGS
+    sort_sections(&mut all_indexes);
+    sort_sections(&mut skipped);
+
+    let rows = stats::compute(&all_indexes);
+    let mut out = std::io::stdout().lock();
+    if csv {
+        stats::write_csv(&mut out, &rows)?;
+    } else {
+        stats::write_table(&mut out, &rows)?;
+    }
+
+    finish(&skipped, args)
+}
+
+fn run_outline(file: &Path, args: &Args) -> Result<()> {
+    if file.is_dir() {
+        bail!("outline takes a single file, not a directory: {}", file.display());
+    }
+    // Unlike a TAGS run, a file that cannot be read is an error: an empty
+    // outline would look like a file without items
+    let (path, content) = if file == Path::new("-") {
+        let mut content = String::new();
+        std::io::stdin()
+            .read_to_string(&mut content)
+            .context("Failed to read source from stdin")?;
+        (args.stdin_filename.clone().unwrap_or_else(|| PathBuf::from("-")), content)
+    } else {
+        let content = fs::read_to_string(file)
+            .with_context(|| format!("Failed to read file: {}", file.display()))?;
+        (file.to_path_buf(), content)
+    };
+
+    let mut skipped: Vec<(PathBuf, Failure)> = Vec::new();
+    let tags = match process_source(&path, &content, args) {
+        Ok((tags, _, failure)) => {
+            skipped.extend(failure.map(|failure| (path.clone(), failure)));
+            tags
+        }
+        Err(e) => {
+            skipped.push((path.clone(), Failure::from_error(&e)));
+            Vec::new()
+        }
+    };
+    println!("{}", serde_json::to_string_pretty(&outline_json(&tags))?);
+
+    finish(&skipped, args)
+}
+
+/// Tags as a JSON tree: every tag holds the tags within its item range, so
+/// methods sit in their impl, trait items in their trait and variants in
+/// their enum
+fn outline_json(tags: &[Tag]) -> serde_json::Value {
+    fn outline_node(node: &tag_tree::Node<&Tag>) -> serde_json::Value {
+        let tag = node.item;
+        serde_json::json!({
+            "name": tag.name,
+            "kind": tag.kind.as_str(),
+            "mode": tag.mode,
+            "line": tag.line,
+            "column": tag.column,
+            "byte_offset": tag.byte_offset,
+            "start_line": tag.start_line,
+            "start_byte": tag.start_byte,
+            "end_line": tag.end_line,
+            "end_byte": tag.end_byte,
+            "children": node.children.iter().map(outline_node).collect::<Vec<_>>(),
+        })
+    }
+    let entries = tags.iter().map(|tag| (tag.start_byte, tag.end_byte, tag)).collect();
+    serde_json::Value::Array(tag_tree::nest(entries).iter().map(outline_node).collect())
+}
+
+fn run_which(positions: &[String], args: &Args) -> Result<()> {
+    let mut wanted = Vec::new();
+    if positions.is_empty() || positions.iter().any(|position| position == "-") {
+        for line in std::io::stdin().lines() {
+            let line = line.context("Failed to read positions from stdin")?;
+            wanted.extend(line.split_whitespace().filter_map(which::parse_position));
+        }
+    }
+    for position in positions.iter().filter(|position| *position != "-") {
+        match which::parse_position(position) {
+            Some(parsed) => wanted.push(parsed),
+            None => bail!("Expected FILE.rs:LINE, got: {}", position),
+        }
+    }
+
+    // Each file is tagged once, however many positions it has
+    let mut files: BTreeMap<PathBuf, Option<Vec<Tag>>> = BTreeMap::new();
+    let mut out = std::io::stdout().lock();
+    for (path, line) in wanted {
+        let tags = files.entry(path.clone()).or_insert_with(|| match process_file(&path, args) {
+            Ok((tags, _, _)) => Some(tags),
+            Err(e) => {
+                eprintln!("Warning: {:#}", e);
+                None
+            }
+        });
+        let Some(tags) = tags else {
+            continue;
+        };
+        let chain: Vec<String> = which::enclosing_items(tags, line).into_iter().map(which::label).collect();
+        if chain.is_empty() {
+            writeln!(out, "{}:{}: (top level)", path.display(), line)?;
+        } else {
+            writeln!(out, "{}:{}: {}", path.display(), line, chain.join(" > "))?;
+        }
+    }
+    Ok(())
+}
+
+/// Index the workspace, then answer LSP requests from it, re-tagging
+/// documents as the editor opens, changes and saves them
+fn run_lsp(paths: &[PathBuf], args: &Args) -> Result<()> {
+    if paths.iter().any(|path| path == Path::new("-")) {
+        bail!("lsp reads the protocol from stdin, so it cannot also read source from it");
+    }
+    let mut all_tags: Vec<(PathBuf, Vec<Tag>)> = Vec::new();
+    let mut all_indexes: Vec<(PathBuf, VerusIndex)> = Vec::new();
+    let mut skipped: Vec<(PathBuf, Failure)> = Vec::new();
+    process_paths(paths, args, &mut all_tags, &mut all_indexes, &mut skipped)?;
+    sort_sections(&mut skipped);
+    // stdout carries the protocol, so diagnostics only go to stderr
+    diagnostics::report_skipped(&skipped, args.error_format);
+
+    let mut indexes: BTreeMap<PathBuf, VerusIndex> = all_indexes.into_iter().collect();
+    let indexed = all_tags
+        .into_iter()
+        .map(|(path, tags)| {
+            let index = indexes.remove(&path).unwrap_or_default();
+            (path, tags, index)
+        })
+        .collect();
+    lsp::serve(indexed, |path, content| {
+        let (tags, index, _) = process_source(path, content, args).ok()?;
+        Some((tags, index))
+    })
+}
+
+/// Files named in the run that no longer exist, or that could not be
+/// tagged at all; an appended SQLite database drops their rows
+fn untagged_files<'p>(
+    named: impl Iterator<Item = &'p PathBuf>,
+    all_tags: &[(PathBuf, Vec<Tag>)],
+    skipped: &'p [(PathBuf, Failure)],
+) -> Vec<PathBuf> {
+    let tagged: BTreeSet<&PathBuf> = all_tags.iter().map(|(path, _)| path).collect();
+    let missing = named.filter(|path| is_rust_file(path) && !path.exists());
+    let failed = skipped.iter().map(|(path, _)| path).filter(|path| !tagged.contains(path));
+    missing.chain(failed).cloned().collect()
+}
+
+/// File names listed one per line (or NUL-separated), as `git ls-files` or `fd` print them
+fn read_file_list(list_path: &Path, null: bool) -> Result<Vec<PathBuf>> {
+    let mut content = Vec::new();
+    if list_path == Path::new("-") {
+        std::io::stdin()
+            .read_to_end(&mut content)
+            .context("Failed to read file list from stdin")?;
+    } else {
+        content = fs::read(list_path)
+            .with_context(|| format!("Failed to read file list: {}", list_path.display()))?;
+    }
+
+    let separator = if null { b'\0' } else { b'\n' };
+    Ok(content
+        .split(|&byte| byte == separator)
+        .map(|name| name.strip_suffix(b"\r").unwrap_or(name))
+        .filter(|name| !name.is_empty())
+        .map(|name| PathBuf::from(String::from_utf8_lossy(name).into_owned()))
+        .collect())
+}
+
 fn is_rust_file(path: &Path) -> bool {
     // Skip Emacs temporary files
     if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
@@ -179,31 +547,315 @@ fn is_rust_file(path: &Path) -> bool {
     path.extension().map_or(false, |ext| ext == "rs")
 }
 
-fn process_file(path: &Path) -> Result<Vec<Tag>> {
+fn process_paths(
+    paths: &[PathBuf],
+    args: &Args,
+    all_tags: &mut Vec<(PathBuf, Vec<Tag>)>,
+    all_indexes: &mut Vec<(PathBuf, VerusIndex)>,
+    skipped: &mut Vec<(PathBuf, Failure)>,
+) -> Result<()> {
+    // Determine if we should recurse
+    let should_recurse = args.recurse && !args.no_recurse;
+
+    // Collect all Rust files
+    for path in paths {
+        if path == Path::new("-") {
+            add_stdin(args, all_tags, all_indexes, skipped)?;
+        } else if path.is_file() {
+            // Files named on the command line are always processed
+            if is_rust_file(path) {
+                add_file(path, args, all_tags, all_indexes, skipped);
+            }
+        } else if path.is_dir() {
+            let mut walker = WalkBuilder::new(path);
+            walker
+                .follow_links(true)
+                // Hidden files and directories are always skipped
+                .standard_filters(!args.no_ignore)
+                .hidden(true)
+                // Honor .gitignore even outside a git checkout
+                .require_git(false)
+                .overrides(build_overrides(path, args)?);
+            if !should_recurse {
+                // Non-recursive: only process files in the immediate directory
+                walker.max_depth(Some(1));
+            }
+            let skip_target = !args.no_ignore;
+            walker.filter_entry(move |e| {
+                if let Some(name) = e.file_name().to_str() {
+                    // Skip Emacs temp files
+                    if name.starts_with(".#") || name.ends_with('~') ||
+                       (name.starts_with('#') && name.ends_with('#')) {
+                        return false;
+                    }
+                }
+                !(skip_target && is_cargo_target_dir(e.path()))
+            });
+
+            for entry in walker.build() {
+                let entry = match entry {
+                    Ok(e) => e,
+                    Err(_) => continue, // Skip entries that can't be read (broken symlinks, etc.)
+                };
+                let file_path = entry.path();
+                if file_path.is_file() && is_rust_file(file_path) {
+                    add_file(file_path, args, all_tags, all_indexes, skipped);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Turn --include/--exclude globs into overrides rooted at a directory argument
+fn build_overrides(root: &Path, args: &Args) -> Result<Override> {
+    let mut builder = OverrideBuilder::new(root);
+    for glob in &args.include {
+        builder
+            .add(glob)
+            .with_context(|| format!("Invalid --include glob: {}", glob))?;
+    }
+    for glob in &args.exclude {
+        builder
+            .add(&format!("!{}", glob))
+            .with_context(|| format!("Invalid --exclude glob: {}", glob))?;
+    }
+    Ok(builder.build()?)
+}
+
+/// Cargo's build directory: a `target` directory next to a Cargo.toml, or
+/// any directory Cargo has marked with a CACHEDIR.TAG, whatever its name
+fn is_cargo_target_dir(path: &Path) -> bool {
+    if !path.is_dir() {
+        return false;
+    }
+    let is_cache_dir = fs::read(path.join("CACHEDIR.TAG"))
+        .is_ok_and(|tag| tag.starts_with(b"Signature: 8a477f597d28d172789f06886806bc55"));
+    is_cache_dir
+        || (path.file_name().is_some_and(|name| name == "target")
+            && path.parent().is_some_and(|parent| parent.join("Cargo.toml").exists()))
+}
+
+fn add_file(
+    path: &Path,
+    args: &Args,
+    all_tags: &mut Vec<(PathBuf, Vec<Tag>)>,
+    all_indexes: &mut Vec<(PathBuf, VerusIndex)>,
+    skipped: &mut Vec<(PathBuf, Failure)>,
+) {
+    if args.verbose_mode {
+        eprintln!("Processing file: {}", path.display());
+    }
+    match process_file(path, args) {
+        Ok((tags, index, failure)) => {
+            all_tags.push((path.to_path_buf(), tags));
+            all_indexes.push((path.to_path_buf(), index));
+            skipped.extend(failure.map(|failure| (path.to_path_buf(), failure)));
+        }
+        Err(e) => skipped.push((path.to_path_buf(), Failure::from_error(&e))),
+    }
+}
+
+/// Tag source read from stdin, such as an unsaved editor buffer, under
+/// the --stdin-filename name
+fn add_stdin(
+    args: &Args,
+    all_tags: &mut Vec<(PathBuf, Vec<Tag>)>,
+    all_indexes: &mut Vec<(PathBuf, VerusIndex)>,
+    skipped: &mut Vec<(PathBuf, Failure)>,
+) -> Result<()> {
+    let mut content = String::new();
+    std::io::stdin()
+        .read_to_string(&mut content)
+        .context("Failed to read source from stdin")?;
+    let path = args.stdin_filename.clone().unwrap_or_else(|| PathBuf::from("-"));
+
+    if args.verbose_mode {
+        eprintln!("Processing stdin as: {}", path.display());
+    }
+    match process_source(&path, &content, args) {
+        Ok((tags, index, failure)) => {
+            all_tags.push((path.clone(), tags));
+            all_indexes.push((path.clone(), index));
+            skipped.extend(failure.map(|failure| (path, failure)));
+        }
+        Err(e) => skipped.push((path, Failure::from_error(&e))),
+    }
+    Ok(())
+}
+
+/// Tags and index of a file, and the parse failure if only part of it could be tagged
+type ProcessedFile = (Vec<Tag>, VerusIndex, Option<Failure>);
+
+fn process_file(path: &Path, args: &Args) -> Result<ProcessedFile> {
     let content = fs::read_to_string(path)
         .with_context(|| format!("Failed to read file: {}", path.display()))?;
+    process_source(path, &content, args)
+}
 
+fn process_source(path: &Path, content: &str, args: &Args) -> Result<ProcessedFile> {
     // Try verus_syn first (handles Verus-specific syntax)
-    if let Ok(syntax_tree) = verus_syn::parse_file(&content) {
-        let mut visitor = TagVisitor::new(&content);
-        visitor.visit_file(&syntax_tree);
-        
-        // Also try to extract tags from verus! macro invocations
-        visitor.process_verus_macros(&syntax_tree);
+    let verus_error = match verus_syn::parse_file(content) {
+        Ok(syntax_tree) => return Ok(process_verus_file(&syntax_tree, content, args)),
+        Err(error) => error,
+    };
 
-        return Ok(visitor.tags());
+    // Syntax from an older or newer Verus release than the bundled verus_syn:
+    // tag the file as if it were absent rather than losing its verus! tags
+    if let Some((syntax_tree, syntax)) = syntax_generations::parse_file_tolerant(content) {
+        let (tags, index, item_failure) = process_verus_file(&syntax_tree, content, args);
+        let mut failure = Failure::from_syn(&verus_error);
+        failure.recovered = true;
+        if let Some(item_failure) = item_failure {
+            failure.unparsed_lines = item_failure.unparsed_lines;
+            failure.add_syntax(item_failure.syntax);
+        }
+        failure.add_syntax(syntax);
+        return Ok((tags, index, Some(failure)));
     }
 
     // Fall back to regular syn parser (for pure Rust files like compiler internals)
-    let syntax_tree = syn::parse_file(&content)
-        .with_context(|| format!("Failed to parse file with both verus_syn and syn: {}", path.display()))?;
+    if let Ok(tags) = process_file_regular_syn(content) {
+        return Ok((tags, VerusIndex::default(), None));
+    }
+
+    // Neither parser accepts the whole file. Report verus_syn's error, since it
+    // accepts a superset of Rust, and tag whatever parses item by item.
+    let mut failure = Failure::from_syn(&verus_error);
+    let recovered = recovery::recover(content);
+    // Its verus! bodies hold only items that parse, so there is nothing more to report
+    let (mut tags, index, _) = process_verus_file(&recovered.verus_file, content, args);
+    let mut visitor = TagVisitor::new(content);
+    visitor.visit_file_regular_syn(&recovered.syn_file);
+    for (name, kind, line) in recovered.scanned {
+        visitor.add_line_tag(name, kind, line);
+    }
+    tags.extend(visitor.tags());
+
+    if tags.is_empty() {
+        return Err(anyhow::Error::new(failure))
+            .with_context(|| format!("Failed to parse file with both verus_syn and syn: {}", path.display()));
+    }
+    failure.recovered = true;
+    failure.unparsed_lines = recovered.failed_lines;
+    failure.add_syntax(recovered.syntax);
+    Ok((tags, index, Some(failure)))
+}
 
-    let mut visitor = TagVisitor::new(&content);
+fn process_verus_file(syntax_tree: &verus_syn::File, content: &str, args: &Args) -> ProcessedFile {
+    // Parse verus! bodies item by item, so an item verus_syn does not
+    // understand only loses its own tags, and tag and index them
+    let bodies = VerusBodies::parse(&syntax_tree.items);
+
+    let mut visitor = TagVisitor::new(content);
+    visitor.visit_file(syntax_tree);
+    visitor.process_verus_macros(syntax_tree, &bodies);
+
+    // Broadcast groups, uses, triggers and calls, including those inside verus! bodies
+    let index = VerusIndex::collect(syntax_tree, &bodies);
+    visitor.add_index_tags(&index);
+    if args.trigger_tags {
+        visitor.add_trigger_tags(&index);
+    }
+
+    (visitor.tags(), index, Failure::from_item_errors(bodies.errors()))
+}
+
+fn process_file_regular_syn(content: &str) -> Result<Vec<Tag>> {
+    let syntax_tree = syn::parse_file(content)?;
+
+    let mut visitor = TagVisitor::new(content);
     visitor.visit_file_regular_syn(&syntax_tree);
 
     Ok(visitor.tags())
 }
 
+/// The directory whose `rust-toolchain.toml` applies: that of
+/// `--manifest-path`, or of the first input path
+fn project_dir(args: &Args) -> PathBuf {
+    let anchor = args
+        .manifest_path
+        .as_deref()
+        .or_else(|| args.paths.first().map(PathBuf::as_path).filter(|path| *path != Path::new("-")));
+    match anchor {
+        Some(path) if path.is_dir() => path.to_path_buf(),
+        Some(path) => match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        },
+        None => PathBuf::from("."),
+    }
+}
+
+/// Tag the toolchain's core/alloc/std sources with plain syn, reusing the
+/// cached tags file if this toolchain has been tagged before
+fn std_tags(args: &Args) -> Result<PathBuf> {
+    let sources = sysroot::locate_std(&project_dir(args))?;
+    let cache_path = sysroot::std_cache_path(&sources, args.sort)?;
+    if cache_path.exists() {
+        if args.verbose_mode {
+            eprintln!("Using cached standard library tags: {}", cache_path.display());
+        }
+        return Ok(cache_path);
+    }
+
+    let mut std_tags: Vec<(PathBuf, Vec<Tag>)> = Vec::new();
+    for krate in sysroot::STD_CRATES {
+        let manifest_path = sources.library_dir.join(krate).join("Cargo.toml");
+        for file_path in cargo_discovery::library_files(&manifest_path)? {
+            if args.verbose_mode {
+                eprintln!("Processing file: {}", file_path.display());
+            }
+            let tags = fs::read_to_string(&file_path)
+                .map_err(anyhow::Error::from)
+                .and_then(|content| process_file_regular_syn(&content));
+            match tags {
+                Ok(tags) => std_tags.push((file_path, tags)),
+                Err(e) => {
+                    if args.verbose_mode {
+                        eprintln!("Warning: Skipping file {}: {}", file_path.display(), e);
+                    }
+                }
+            }
+        }
+    }
+    sort_sections(&mut std_tags);
+    if args.sort > 0 {
+        sort_tags(&mut std_tags, args.sort == 2);
+    }
+
+    // Write under a temporary name so an interrupted run leaves no partial cache
+    if let Some(cache_dir) = cache_path.parent() {
+        fs::create_dir_all(cache_dir)
+            .with_context(|| format!("Failed to create cache directory: {}", cache_dir.display()))?;
+    }
+    let mut partial_path = cache_path.clone().into_os_string();
+    partial_path.push(".partial");
+    let partial_path = PathBuf::from(partial_path);
+    // The cache is shared between projects, so it always holds absolute names
+    let style = PathStyle {
+        absolute: true,
+        ..PathStyle::default()
+    };
+    write_etags(&partial_path, &std_tags, &[], &style, false)?;
+    fs::rename(&partial_path, &cache_path)
+        .with_context(|| format!("Failed to write cache file: {}", cache_path.display()))?;
+
+    if args.verbose_mode {
+        eprintln!("Generated {} with {} files", cache_path.display(), std_tags.len());
+    }
+    Ok(cache_path)
+}
+
+/// Order file sections by name and drop files reached more than once
+/// (through symlinks or overlapping arguments), keeping the smallest name
+fn sort_sections<T>(sections: &mut Vec<(PathBuf, T)>) {
+    sections.sort_by(|(a, _), (b, _)| a.cmp(b));
+    let mut seen = BTreeSet::new();
+    sections.retain(|(path, _)| seen.insert(path.canonicalize().unwrap_or_else(|_| path.clone())));
+}
+
 fn sort_tags(all_tags: &mut Vec<(PathBuf, Vec<Tag>)>, foldcase: bool) {
     for (_path, tags) in all_tags.iter_mut() {
         tags.sort_by(|a, b| {
@@ -223,9 +875,133 @@ fn sort_tags(all_tags: &mut Vec<(PathBuf, Vec<Tag>)>, foldcase: bool) {
     }
 }
 
-fn write_etags(output_path: &Path, all_tags: &[(PathBuf, Vec<Tag>)], _append: bool) -> Result<()> {
-    let mut file = fs::File::create(output_path)
-        .with_context(|| format!("Failed to create output file: {}", output_path.display()))?;
+/// Tag every locally available dependency of the workspace into a separate tags file
+fn write_dependency_tags(
+    deps_path: &Path,
+    manifest_path: &Path,
+    style: &PathStyle,
+    args: &Args,
+) -> Result<()> {
+    let sources = cargo_deps::locate(manifest_path)?;
+    if !sources.missing.is_empty() {
+        eprintln!(
+            "Warning: {} locked dependencies have no local source (run `cargo fetch` or `cargo vendor`)",
+            sources.missing.len()
+        );
+        if args.verbose_mode {
+            for package in &sources.missing {
+                eprintln!("  {} {} ({})", package.name, package.version, package.source);
+            }
+        }
+    }
+
+    let mut deps_tags: Vec<(PathBuf, Vec<Tag>)> = Vec::new();
+    let mut deps_indexes: Vec<(PathBuf, VerusIndex)> = Vec::new();
+    // Files of dependencies that fail to parse are not the project's problem,
+    // so they are neither summarized nor counted by --strict
+    let mut deps_skipped: Vec<(PathBuf, Failure)> = Vec::new();
+    for dep_manifest in &sources.manifests {
+        for file_path in cargo_discovery::library_files(dep_manifest)? {
+            add_file(&file_path, args, &mut deps_tags, &mut deps_indexes, &mut deps_skipped);
+        }
+    }
+    if args.verbose_mode {
+        for (file_path, failure) in &deps_skipped {
+            eprintln!("Warning: Skipping file {}: {}", file_path.display(), failure);
+        }
+    }
+    sort_sections(&mut deps_tags);
+    if args.sort > 0 {
+        sort_tags(&mut deps_tags, args.sort == 2);
+    }
+    write_etags(deps_path, &deps_tags, &[], style, false)?;
+
+    if args.verbose_mode {
+        eprintln!("Generated {} with {} files", deps_path.display(), deps_tags.len());
+    }
+    Ok(())
+}
+
+/// Write a tags file for each crate (the nearest directory holding a
+/// Cargo.toml) or, outside any crate, each top-level directory. Their names
+/// are added to `includes`; sections that belong in the root tags file are
+/// returned.
+fn write_split_tags(
+    output_path: &Path,
+    all_tags: &[(PathBuf, Vec<Tag>)],
+    style: &PathStyle,
+    includes: &mut Vec<PathBuf>,
+) -> Result<Vec<(PathBuf, Vec<Tag>)>> {
+    let output_dir = output_path.parent().unwrap_or(Path::new(""));
+    let root_dir = canonical_dir(output_dir);
+    let tags_name = output_path.file_name().unwrap_or("TAGS".as_ref());
+
+    let mut root_tags = Vec::new();
+    let mut groups: BTreeMap<PathBuf, Vec<(PathBuf, Vec<Tag>)>> = BTreeMap::new();
+    for (file_path, tags) in all_tags {
+        match split_group(file_path) {
+            Some(group_dir) if canonical_dir(&group_dir) != root_dir => {
+                groups
+                    .entry(group_dir)
+                    .or_default()
+                    .push((file_path.clone(), tags.clone()));
+            }
+            _ => root_tags.push((file_path.clone(), tags.clone())),
+        }
+    }
+
+    // Unless absolute names were asked for, each per-crate file names its
+    // sources relative to itself so that it stays valid on its own
+    let group_style = PathStyle {
+        tag_relative: !style.absolute,
+        ..style.clone()
+    };
+    for (group_dir, group_tags) in groups {
+        let group_output = group_dir.join(tags_name);
+        write_etags(&group_output, &group_tags, &[], &group_style, false)?;
+        includes.push(group_output);
+    }
+    Ok(root_tags)
+}
+
+/// Crate directory of a file, or its top-level directory if it is in no crate
+fn split_group(file_path: &Path) -> Option<PathBuf> {
+    let crate_dir = file_path
+        .ancestors()
+        .skip(1)
+        .find(|dir| dir.join("Cargo.toml").is_file());
+    if let Some(crate_dir) = crate_dir {
+        return Some(crate_dir.to_path_buf());
+    }
+
+    let mut components = file_path
+        .components()
+        .skip_while(|component| matches!(component, std::path::Component::CurDir));
+    match (components.next(), components.next()) {
+        (Some(std::path::Component::Normal(top)), Some(_)) => Some(PathBuf::from(top)),
+        _ => None,
+    }
+}
+
+fn canonical_dir(dir: &Path) -> PathBuf {
+    let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+    dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf())
+}
+
+fn write_etags(
+    output_path: &Path,
+    all_tags: &[(PathBuf, Vec<Tag>)],
+    includes: &[PathBuf],
+    style: &PathStyle,
+    _append: bool,
+) -> Result<()> {
+    let mut file = create_output(output_path)?;
+
+    // Include sections: \x0c\n<tags file>,include\n
+    for include in includes {
+        let name = style.include_name(include, output_path);
+        write!(file, "\x0c\n{},include\n", name.display())?;
+    }
 
     for (file_path, tags) in all_tags {
         if tags.is_empty() {
@@ -246,12 +1022,71 @@ fn write_etags(output_path: &Path, all_tags: &[(PathBuf, Vec<Tag>)], _append: bo
         let section_size = section_content.len();
 
         // Write the file header: \x0c\n<filename>,<section_size>\n
-        write!(file, "\x0c\n{},{}\n", file_path.display(), section_size)?;
+        let name = style.source_name(file_path, output_path);
+        write!(file, "\x0c\n{},{}\n", name.display(), section_size)?;
 
         // Write the tag entries
         file.write_all(&section_content)?;
     }
 
+    file.flush()?;
+    Ok(())
+}
+
+/// One JSON object per tag, with the fields of the etags entry and the
+/// metadata etags has no room for
+fn write_json_tags(output_path: &Path, all_tags: &[(PathBuf, Vec<Tag>)], style: &PathStyle) -> Result<()> {
+    let mut file = create_output(output_path)?;
+
+    for (file_path, tags) in all_tags {
+        let name = style.source_name(file_path, output_path);
+        for tag in tags {
+            let record = serde_json::json!({
+                "file": name,
+                "name": tag.name,
+                "kind": tag.kind.as_str(),
+                "line": tag.line,
+                "column": tag.column,
+                "byte_offset": tag.byte_offset,
+                "start_line": tag.start_line,
+                "start_byte": tag.start_byte,
+                "end_line": tag.end_line,
+                "end_byte": tag.end_byte,
+                "pattern": tag.pattern,
+                "scope": tag.scope,
+                "mode": tag.mode,
+            });
+            writeln!(file, "{}", record)?;
+        }
+    }
+
+    file.flush()?;
+    Ok(())
+}
+
+/// The output file, or stdout for "-"
+fn create_output(output_path: &Path) -> Result<Box<dyn Write>> {
+    if output_path == Path::new("-") {
+        return Ok(Box::new(std::io::stdout().lock()));
+    }
+    let file = fs::File::create(output_path)
+        .with_context(|| format!("Failed to create output file: {}", output_path.display()))?;
+    Ok(Box::new(file))
+}
+
+fn write_unused_report(report_path: &Path, all_indexes: &[(PathBuf, VerusIndex)]) -> Result<()> {
+    let mut file = fs::File::create(report_path)
+        .with_context(|| format!("Failed to create unused report: {}", report_path.display()))?;
+
+    // Format: <file>:<line>: unused <mode> fn <name>
+    for (file_path, function) in verus_index::unused_functions(all_indexes) {
+        writeln!(
+            file,
+            "{}:{}: unused {} fn {}",
+            file_path.display(), function.line, function.mode, function.name
+        )?;
+    }
+
     Ok(())
 }
 
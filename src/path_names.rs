@@ -0,0 +1,138 @@
+use std::env;
+use std::path::{Component, Path, PathBuf};
+
+/// How file names are written into a tags file
+#[derive(Debug, Clone, Default)]
+pub struct PathStyle {
+    /// Relative to the directory of the tags file, as etags writes them
+    pub tag_relative: bool,
+    pub absolute: bool,
+    /// `--path-map FROM=TO` prefix rewrites, applied last; the first match wins
+    pub path_map: Vec<(PathBuf, PathBuf)>,
+}
+
+impl PathStyle {
+    /// Name of a tagged source file inside the tags file at `tags_path`.
+    /// Without --absolute or --tag-relative the name is kept as discovered.
+    pub fn source_name(&self, path: &Path, tags_path: &Path) -> PathBuf {
+        let path = normalize(path);
+        let name = if self.absolute {
+            absolute(&path)
+        } else if self.tag_relative {
+            relative_to(&absolute(&path), &tags_dir(tags_path))
+        } else {
+            path
+        };
+        self.map(name)
+    }
+
+    /// Name of an included tags file inside the tags file at `tags_path`.
+    /// By default it is relative when the included file lies beneath the
+    /// including file's directory, and absolute otherwise.
+    pub fn include_name(&self, path: &Path, tags_path: &Path) -> PathBuf {
+        let path = absolute(path);
+        let dir = tags_dir(tags_path);
+        let name = if self.absolute {
+            path
+        } else if self.tag_relative {
+            relative_to(&path, &dir)
+        } else {
+            match path.strip_prefix(&dir) {
+                Ok(relative) => relative.to_path_buf(),
+                Err(_) => path,
+            }
+        };
+        self.map(name)
+    }
+
+    fn map(&self, name: PathBuf) -> PathBuf {
+        for (from, to) in &self.path_map {
+            if let Ok(rest) = name.strip_prefix(from) {
+                return if rest.as_os_str().is_empty() {
+                    to.clone()
+                } else {
+                    to.join(rest)
+                };
+            }
+        }
+        name
+    }
+}
+
+/// Parse a `--path-map FROM=TO` argument
+pub fn parse_path_map(value: &str) -> Result<(PathBuf, PathBuf), String> {
+    match value.split_once('=') {
+        Some((from, to)) if !from.is_empty() => Ok((normalize(Path::new(from)), PathBuf::from(to))),
+        _ => Err(format!("expected FROM=TO, got `{}`", value)),
+    }
+}
+
+/// Drop `.` components and resolve `..` against the preceding component,
+/// without touching the file system. Leading `..` components are kept.
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                let can_pop = matches!(normalized.components().next_back(), Some(Component::Normal(_)));
+                if can_pop {
+                    normalized.pop();
+                } else if !normalized.has_root() {
+                    normalized.push("..");
+                }
+            }
+            other => normalized.push(other),
+        }
+    }
+    if normalized.as_os_str().is_empty() {
+        normalized.push(".");
+    }
+    normalized
+}
+
+/// `path` made absolute against the current directory, and normalized
+pub fn absolute(path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        return normalize(path);
+    }
+    match env::current_dir() {
+        Ok(cwd) => normalize(&cwd.join(path)),
+        Err(_) => normalize(path),
+    }
+}
+
+/// Absolute directory of a tags file; stdout counts as the current directory
+fn tags_dir(tags_path: &Path) -> PathBuf {
+    match tags_path.parent() {
+        Some(dir) if tags_path != Path::new("-") && !dir.as_os_str().is_empty() => absolute(dir),
+        _ => absolute(Path::new(".")),
+    }
+}
+
+/// `path` relative to `base`; both must be absolute and normalized
+fn relative_to(path: &Path, base: &Path) -> PathBuf {
+    let path_components: Vec<Component> = path.components().collect();
+    let base_components: Vec<Component> = base.components().collect();
+    let common = path_components
+        .iter()
+        .zip(&base_components)
+        .take_while(|(a, b)| a == b)
+        .count();
+    // Different roots (e.g. Windows drives) cannot be made relative
+    if common == 0 {
+        return path.to_path_buf();
+    }
+
+    let mut relative = PathBuf::new();
+    for _ in common..base_components.len() {
+        relative.push("..");
+    }
+    for component in &path_components[common..] {
+        relative.push(component);
+    }
+    if relative.as_os_str().is_empty() {
+        relative.push(".");
+    }
+    relative
+}
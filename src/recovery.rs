@@ -0,0 +1,402 @@
+use proc_macro2::{Delimiter, Group, LineColumn, TokenStream, TokenTree};
+use std::collections::HashMap;
+use verus_syn::spanned::Spanned;
+use verus_syn::visit::Visit;
+
+use crate::syntax_generations::{self, SyntaxChange};
+use crate::tag_visitor::{is_verus_macro, TagKind};
+
+/// What could be salvaged from a file
+#[derive(Debug)]
+pub struct Recovered {
+    /// Items verus_syn accepts, with `verus!` bodies reduced to their parseable items
+    pub verus_file: verus_syn::File,
+    /// Items only plain syn accepts
+    pub syn_file: syn::File,
+    /// `(name, kind, line)` of definitions found by the keyword scanner
+    pub scanned: Vec<(String, TagKind, usize)>,
+    /// First line of every item or region that could not be parsed
+    pub failed_lines: Vec<usize>,
+    /// Syntax of other Verus generations that items were parsed without
+    pub syntax: Vec<SyntaxChange>,
+}
+
+/// A `verus!` body item verus_syn rejected
+#[derive(Debug)]
+pub struct ItemError {
+    /// First line of the item
+    pub line: usize,
+    pub error: verus_syn::Error,
+    /// Syntax of other Verus generations the item was parsed without; if
+    /// empty, the item could not be parsed and was skipped
+    pub syntax: Vec<SyntaxChange>,
+}
+
+/// Words that may precede the keyword that decides an item's kind
+const ITEM_MODIFIERS: [&str; 16] = [
+    "pub", "unsafe", "async", "extern", "default", "open", "closed", "spec", "proof", "exec",
+    "tracked", "ghost", "broadcast", "uninterp", "axiom", "auto",
+];
+
+/// Salvage what can be tagged from a file that neither verus_syn nor syn
+/// accepts as a whole. The file is cut into top-level items at token-tree
+/// boundaries and each item is parsed on its own, as are the items of
+/// `verus!` bodies, so a syntax error costs one item instead of the whole
+/// file. Items that still fail go through the keyword scanner.
+pub fn recover(content: &str) -> Recovered {
+    let mut failed_lines = Vec::new();
+    let mut scanned = Vec::new();
+    let mut syntax = Vec::new();
+    let mut verus_items = Vec::new();
+    let mut syn_items = Vec::new();
+
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let mut regions = Vec::new();
+    lex_region(&lines, 1, &mut regions, &mut failed_lines, &mut scanned);
+
+    for region in regions {
+        for item in split_items(region) {
+            if is_inner_attribute(&item) {
+                continue;
+            }
+            if let Some(macro_item) = recover_verus_macro(&item, &mut scanned, &mut failed_lines, &mut syntax) {
+                match verus_syn::parse2::<verus_syn::Item>(macro_item) {
+                    Ok(parsed) => verus_items.push(parsed),
+                    Err(_) => failed_lines.extend(first_line(&item)),
+                }
+            } else if let Ok(parsed) = verus_syn::parse2::<verus_syn::Item>(item.clone()) {
+                verus_items.push(parsed);
+            } else if let Ok(parsed) = syn::parse2::<syn::Item>(item.clone()) {
+                syn_items.push(parsed);
+            } else if let Some((parsed, changes)) = syntax_generations::parse_tolerant::<verus_syn::Item>(item.clone()) {
+                verus_items.push(parsed);
+                syntax.extend(changes);
+            } else {
+                failed_lines.extend(first_line(&item));
+                scan_definitions(item, &mut scanned);
+            }
+        }
+    }
+
+    failed_lines.sort_unstable();
+
+    Recovered {
+        verus_file: verus_syn::File {
+            shebang: None,
+            attrs: Vec::new(),
+            items: verus_items,
+        },
+        syn_file: syn::File {
+            shebang: None,
+            attrs: Vec::new(),
+            items: syn_items,
+        },
+        scanned,
+        failed_lines,
+        syntax,
+    }
+}
+
+/// Tokenize `lines` (starting at line `first_line` of the file). If the
+/// delimiters are unbalanced, cut the region at the lines that start its
+/// outermost items and try each part, so only the part holding the
+/// imbalance is lost; its definitions still go through the keyword scanner.
+fn lex_region(
+    lines: &[&str],
+    first_line: usize,
+    regions: &mut Vec<TokenStream>,
+    failed_lines: &mut Vec<usize>,
+    scanned: &mut Vec<(String, TagKind, usize)>,
+) {
+    if lines.iter().all(|line| line.trim().is_empty()) {
+        return;
+    }
+
+    // Leading newlines keep span line numbers equal to those of the file
+    let mut text = "\n".repeat(first_line - 1);
+    text.extend(lines.iter().copied());
+    if let Ok(tokens) = text.parse::<TokenStream>() {
+        regions.push(tokens);
+        return;
+    }
+
+    let boundaries = item_boundaries(lines);
+    if boundaries.is_empty() {
+        failed_lines.push(first_line);
+        scan_lines(lines, first_line, scanned);
+        return;
+    }
+    let mut start = 0;
+    for end in boundaries.into_iter().chain([lines.len()]) {
+        lex_region(&lines[start..end], first_line + start, regions, failed_lines, scanned);
+        start = end;
+    }
+}
+
+/// Indices of the lines after the first that start an item at the
+/// region's outermost indentation, plus lines that close a delimiter
+/// opened outside the region
+fn item_boundaries(lines: &[&str]) -> Vec<usize> {
+    let starts_item = |line: &str| {
+        let code = line.trim_start();
+        !code.is_empty() && !code.starts_with("//") && !code.starts_with(['}', ')', ']'])
+    };
+    let indentation = |line: &str| line.len() - line.trim_start().len();
+
+    let Some(outer) = lines[1..]
+        .iter()
+        .filter(|line| starts_item(line))
+        .map(|line| indentation(line))
+        .min()
+    else {
+        return Vec::new();
+    };
+
+    (1..lines.len())
+        .filter(|&index| {
+            let line = lines[index];
+            let closes_outer = line.trim_start().starts_with(['}', ')', ']']) && indentation(line) <= outer;
+            (starts_item(line) && indentation(line) == outer) || closes_outer
+        })
+        .collect()
+}
+
+/// Cut a token stream into items: an item ends at a top-level `;`, or at a
+/// top-level `{ ... }` unless it is a `const`/`static`/`type`/`use` item,
+/// whose braces belong to an expression, type or use tree. Inner attributes
+/// (`#![...]`) stand alone.
+fn split_items(stream: TokenStream) -> Vec<TokenStream> {
+    let mut items = Vec::new();
+    let mut current: Vec<TokenTree> = Vec::new();
+
+    for token in stream {
+        let ends_item = match &token {
+            TokenTree::Punct(punct) => punct.as_char() == ';',
+            TokenTree::Group(group) => match group.delimiter() {
+                Delimiter::Brace => !ends_at_semicolon(&current),
+                Delimiter::Bracket => is_inner_attribute_start(&current),
+                _ => false,
+            },
+            _ => false,
+        };
+        current.push(token);
+        if ends_item {
+            // A stray `;` after a braced item is not an item of its own
+            if !matches!(current.as_slice(), [TokenTree::Punct(_)]) {
+                items.push(current.iter().cloned().collect());
+            }
+            current.clear();
+        }
+    }
+    if !current.is_empty() {
+        items.push(current.into_iter().collect());
+    }
+    items
+}
+
+fn is_inner_attribute(item: &TokenStream) -> bool {
+    let start: Vec<TokenTree> = item.clone().into_iter().take(2).collect();
+    is_inner_attribute_start(&start)
+}
+
+fn is_inner_attribute_start(current: &[TokenTree]) -> bool {
+    matches!(
+        current,
+        [TokenTree::Punct(pound), TokenTree::Punct(bang)] if pound.as_char() == '#' && bang.as_char() == '!'
+    )
+}
+
+/// Whether the item begun by `current` only ends at a `;`. Its kind is the
+/// first keyword after attributes, visibility and modifiers like `spec`.
+fn ends_at_semicolon(current: &[TokenTree]) -> bool {
+    let mut tokens = current.iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            // Outer attributes and `pub(crate)`/`spec(checked)`-style arguments
+            TokenTree::Punct(punct) if punct.as_char() == '#' => {}
+            TokenTree::Group(_) | TokenTree::Literal(_) => {}
+            TokenTree::Ident(ident) => {
+                let word = ident.to_string();
+                if ITEM_MODIFIERS.contains(&word.as_str()) {
+                    continue;
+                }
+                return match word.as_str() {
+                    // `const fn` is a function
+                    "const" => !matches!(
+                        tokens.peek(),
+                        Some(TokenTree::Ident(next)) if ["fn", "unsafe", "async", "extern"].contains(&next.to_string().as_str())
+                    ),
+                    "static" | "type" | "use" => true,
+                    _ => false,
+                };
+            }
+            TokenTree::Punct(_) => return false,
+        }
+    }
+    false
+}
+
+/// The items of a `verus!` body. If the body does not parse as a whole, each
+/// item is parsed on its own, falling back to parsing it without the syntax
+/// of other Verus generations, so one unsupported construct does not hide
+/// the rest of the block. Items that needed either are reported as errors.
+pub fn verus_body_items(body: TokenStream) -> (Vec<verus_syn::Item>, Vec<ItemError>) {
+    if let Ok(file) = verus_syn::parse2::<verus_syn::File>(body.clone()) {
+        return (file.items, Vec::new());
+    }
+
+    let mut items = Vec::new();
+    let mut errors = Vec::new();
+    for body_item in split_items(body) {
+        let line = first_line(&body_item);
+        let error = match verus_syn::parse2::<verus_syn::Item>(body_item.clone()) {
+            Ok(item) => {
+                items.push(item);
+                continue;
+            }
+            Err(error) => error,
+        };
+        let line = line.unwrap_or_else(|| error.span().start().line);
+        let syntax = match syntax_generations::parse_tolerant::<verus_syn::Item>(body_item) {
+            Some((item, syntax)) => {
+                items.push(item);
+                syntax
+            }
+            None => Vec::new(),
+        };
+        errors.push(ItemError { line, error, syntax });
+    }
+    (items, errors)
+}
+
+/// The items of every `verus!` body in a file, at any depth, each body
+/// parsed once and shared by tagging and indexing
+#[derive(Debug, Default)]
+pub struct VerusBodies {
+    // Keyed by the start of the macro's path, which no two invocations share
+    bodies: HashMap<LineColumn, Vec<verus_syn::Item>>,
+    errors: Vec<ItemError>,
+}
+
+impl VerusBodies {
+    pub fn parse(items: &[verus_syn::Item]) -> Self {
+        let mut bodies = VerusBodies::default();
+        for item in items {
+            bodies.visit_item(item);
+        }
+        bodies
+    }
+
+    /// The parsed body items of a `verus!` macro; empty for other macros
+    pub fn items(&self, mac: &verus_syn::Macro) -> &[verus_syn::Item] {
+        self.bodies
+            .get(&mac.path.span().start())
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// The body items verus_syn rejected
+    pub fn errors(&self) -> &[ItemError] {
+        &self.errors
+    }
+}
+
+impl<'ast> Visit<'ast> for VerusBodies {
+    fn visit_macro(&mut self, node: &'ast verus_syn::Macro) {
+        if !is_verus_macro(node) {
+            return;
+        }
+        let (items, errors) = verus_body_items(node.tokens.clone());
+        // Bodies may hold `verus!` macros of their own
+        let mut nested = VerusBodies::default();
+        for item in &items {
+            nested.visit_item(item);
+        }
+        self.bodies.extend(nested.bodies);
+        self.errors.extend(errors);
+        self.errors.extend(nested.errors);
+        self.bodies.insert(node.path.span().start(), items);
+    }
+}
+
+/// A `verus! { ... }` item rebuilt with only the body items that parse, with
+/// or without the syntax of other Verus generations; the others are scanned
+/// for definitions. `None` if `item` is not a verus! macro.
+fn recover_verus_macro(
+    item: &TokenStream,
+    scanned: &mut Vec<(String, TagKind, usize)>,
+    failed_lines: &mut Vec<usize>,
+    syntax: &mut Vec<SyntaxChange>,
+) -> Option<TokenStream> {
+    let tokens: Vec<TokenTree> = item.clone().into_iter().collect();
+    let [prefix @ .., TokenTree::Ident(name), TokenTree::Punct(bang), TokenTree::Group(body)] = tokens.as_slice() else {
+        return None;
+    };
+    let is_verus = name == "verus" || name == "verus_" || name == "verus_impl";
+    if !is_verus || bang.as_char() != '!' || body.delimiter() != Delimiter::Brace {
+        return None;
+    }
+
+    let mut kept = TokenStream::new();
+    for body_item in split_items(body.stream()) {
+        if verus_syn::parse2::<verus_syn::Item>(body_item.clone()).is_ok() {
+            kept.extend(body_item);
+        } else if let Some((_, changes)) = syntax_generations::parse_tolerant::<verus_syn::Item>(body_item.clone()) {
+            // Kept as written: tagging the body parses it the same way again
+            kept.extend(body_item);
+            syntax.extend(changes);
+        } else {
+            failed_lines.extend(first_line(&body_item));
+            scan_definitions(body_item, scanned);
+        }
+    }
+
+    let mut new_body = Group::new(Delimiter::Brace, kept);
+    new_body.set_span(body.span());
+    let mut rebuilt: TokenStream = prefix.iter().cloned().collect();
+    rebuilt.extend([
+        TokenTree::Ident(name.clone()),
+        TokenTree::Punct(bang.clone()),
+        TokenTree::Group(new_body),
+    ]);
+    Some(rebuilt)
+}
+
+/// `scan_definitions` for text that does not lex as a whole: each line is
+/// lexed on its own, without its delimiters, which are what is unbalanced.
+/// Lines that still do not lex, like those inside a string, are passed over.
+fn scan_lines(lines: &[&str], first_line: usize, scanned: &mut Vec<(String, TagKind, usize)>) {
+    for (index, line) in lines.iter().enumerate() {
+        let text = line.replace(['{', '}', '(', ')', '[', ']'], " ");
+        let Ok(tokens) = text.parse::<TokenStream>() else {
+            continue;
+        };
+        let mut found = Vec::new();
+        scan_definitions(tokens, &mut found);
+        scanned.extend(found.into_iter().map(|(name, kind, _)| (name, kind, first_line + index)));
+    }
+}
+
+/// `fn name`, `struct name`, `enum name` and `trait name`, at any depth
+fn scan_definitions(stream: TokenStream, scanned: &mut Vec<(String, TagKind, usize)>) {
+    let mut tokens = stream.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Ident(keyword) => {
+                if let (Some(kind), Some(TokenTree::Ident(name))) =
+                    (TagKind::from_keyword(&keyword.to_string()), tokens.peek())
+                {
+                    scanned.push((name.to_string(), kind, name.span().start().line));
+                }
+            }
+            TokenTree::Group(group) => scan_definitions(group.stream(), scanned),
+            _ => {}
+        }
+    }
+}
+
+fn first_line(item: &TokenStream) -> Option<usize> {
+    item.clone()
+        .into_iter()
+        .next()
+        .map(|token| token.span().start().line)
+}
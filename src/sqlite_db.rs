@@ -0,0 +1,147 @@
+use anyhow::{bail, Context, Result};
+use rusqlite::{params, Connection};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::path_names::PathStyle;
+use crate::tag_visitor::Tag;
+use crate::verus_index::VerusIndex;
+
+/// Stored as `PRAGMA user_version`; bump it whenever SCHEMA changes, so
+/// `--append` does not add rows to a database laid out by another version
+const SCHEMA_VERSION: i64 = 1;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS files (
+    id INTEGER PRIMARY KEY,
+    path TEXT NOT NULL UNIQUE
+);
+CREATE TABLE IF NOT EXISTS tags (
+    id INTEGER PRIMARY KEY,
+    file_id INTEGER NOT NULL REFERENCES files(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    line INTEGER NOT NULL,
+    column INTEGER NOT NULL,
+    byte_offset INTEGER NOT NULL,
+    start_line INTEGER NOT NULL,
+    start_byte INTEGER NOT NULL,
+    end_line INTEGER NOT NULL,
+    end_byte INTEGER NOT NULL,
+    pattern TEXT NOT NULL,
+    scope TEXT,
+    mode TEXT
+);
+CREATE TABLE IF NOT EXISTS refs (
+    id INTEGER PRIMARY KEY,
+    file_id INTEGER NOT NULL REFERENCES files(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    line INTEGER NOT NULL,
+    enclosing_fn TEXT
+);
+CREATE INDEX IF NOT EXISTS tags_name ON tags(name);
+CREATE INDEX IF NOT EXISTS tags_file ON tags(file_id);
+CREATE INDEX IF NOT EXISTS refs_name ON refs(name);
+CREATE INDEX IF NOT EXISTS refs_file ON refs(file_id);
+";
+
+/// Write tags and references into a SQLite database. Without `append` the
+/// database is created from scratch; with it, only the rows of the files
+/// tagged in this run are replaced and those of the `removed` files are
+/// deleted, unless the database has another schema version, in which case
+/// it is rebuilt from the tagged files.
+pub fn write_sqlite(
+    output_path: &Path,
+    all_tags: &[(PathBuf, Vec<Tag>)],
+    all_indexes: &[(PathBuf, VerusIndex)],
+    removed: &[PathBuf],
+    style: &PathStyle,
+    append: bool,
+) -> Result<()> {
+    if output_path == Path::new("-") {
+        bail!("--format sqlite cannot write to stdout");
+    }
+    let stale = append && output_path.exists() && schema_version(output_path)? != SCHEMA_VERSION;
+    if stale {
+        eprintln!(
+            "Warning: {} has an older or newer schema; rebuilding it from the files of this run",
+            output_path.display()
+        );
+    }
+    if (!append || stale) && output_path.exists() {
+        fs::remove_file(output_path)
+            .with_context(|| format!("Failed to replace database: {}", output_path.display()))?;
+    }
+
+    let mut connection = Connection::open(output_path)
+        .with_context(|| format!("Failed to open database: {}", output_path.display()))?;
+    connection.execute_batch("PRAGMA foreign_keys = ON;")?;
+    connection
+        .execute_batch(SCHEMA)
+        .with_context(|| format!("Failed to create tables in {}", output_path.display()))?;
+    connection.execute_batch(&format!("PRAGMA user_version = {};", SCHEMA_VERSION))?;
+
+    let indexes: HashMap<&Path, &VerusIndex> =
+        all_indexes.iter().map(|(path, index)| (path.as_path(), index)).collect();
+
+    let transaction = connection.transaction()?;
+    {
+        let mut delete_file = transaction.prepare("DELETE FROM files WHERE path = ?1")?;
+        let mut insert_file = transaction.prepare("INSERT INTO files (path) VALUES (?1)")?;
+        let mut insert_tag = transaction.prepare(
+            "INSERT INTO tags (file_id, name, kind, line, column, byte_offset, start_line, start_byte, end_line, end_byte,
+                               pattern, scope, mode)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+        )?;
+        let mut insert_ref =
+            transaction.prepare("INSERT INTO refs (file_id, name, line, enclosing_fn) VALUES (?1, ?2, ?3, ?4)")?;
+
+        // Their tags and references go with them
+        for file_path in removed {
+            delete_file.execute(params![style.source_name(file_path, output_path).display().to_string()])?;
+        }
+
+        for (file_path, tags) in all_tags {
+            let name = style.source_name(file_path, output_path).display().to_string();
+            delete_file.execute(params![name])?;
+            insert_file.execute(params![name])?;
+            let file_id = transaction.last_insert_rowid();
+
+            for tag in tags {
+                insert_tag.execute(params![
+                    file_id,
+                    tag.name,
+                    tag.kind.as_str(),
+                    tag.line,
+                    tag.column,
+                    tag.byte_offset,
+                    tag.start_line,
+                    tag.start_byte,
+                    tag.end_line,
+                    tag.end_byte,
+                    tag.pattern,
+                    tag.scope,
+                    tag.mode,
+                ])?;
+            }
+
+            // Only files parsed by verus_syn have references
+            for reference in indexes.get(file_path.as_path()).into_iter().flat_map(|index| &index.references) {
+                insert_ref.execute(params![file_id, reference.name, reference.line, reference.enclosing_fn])?;
+            }
+        }
+    }
+    transaction
+        .commit()
+        .with_context(|| format!("Failed to write database: {}", output_path.display()))?;
+    Ok(())
+}
+
+fn schema_version(path: &Path) -> Result<i64> {
+    let connection =
+        Connection::open(path).with_context(|| format!("Failed to open database: {}", path.display()))?;
+    connection
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .with_context(|| format!("Failed to read schema version of {}", path.display()))
+}
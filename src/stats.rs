@@ -0,0 +1,211 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::PathBuf;
+
+use anyhow::Result;
+
+use crate::verus_index::VerusIndex;
+
+/// Function and line counts by Verus mode for one file or module
+#[derive(Debug, Default, Clone)]
+pub struct ModeStats {
+    pub spec_fns: usize,
+    pub proof_fns: usize,
+    pub exec_fns: usize,
+    pub spec_lines: usize,
+    pub proof_lines: usize,
+    pub exec_lines: usize,
+    pub proof_block_lines: usize,
+    pub spec_clause_lines: usize,
+    pub trusted_items: usize,
+    // Proof blocks and spec clauses inside exec fns, already counted in exec_lines
+    ghost_lines_in_exec: usize,
+}
+
+impl ModeStats {
+    fn add(&mut self, other: &ModeStats) {
+        self.spec_fns += other.spec_fns;
+        self.proof_fns += other.proof_fns;
+        self.exec_fns += other.exec_fns;
+        self.spec_lines += other.spec_lines;
+        self.proof_lines += other.proof_lines;
+        self.exec_lines += other.exec_lines;
+        self.proof_block_lines += other.proof_block_lines;
+        self.spec_clause_lines += other.spec_clause_lines;
+        self.trusted_items += other.trusted_items;
+        self.ghost_lines_in_exec += other.ghost_lines_in_exec;
+    }
+
+    /// Lines of spec and proof code per line of executable code
+    pub fn ghost_ratio(&self) -> Option<f64> {
+        let ghost = self.spec_lines + self.proof_lines + self.ghost_lines_in_exec;
+        let exec = self.exec_lines.saturating_sub(self.ghost_lines_in_exec);
+        if exec == 0 {
+            None
+        } else {
+            Some(ghost as f64 / exec as f64)
+        }
+    }
+}
+
+/// One row of the report: a whole file (`module` empty), an inline module, or the total
+#[derive(Debug, Clone)]
+pub struct StatsRow {
+    pub file: String,
+    pub module: String,
+    pub stats: ModeStats,
+}
+
+fn module_stats(index: &VerusIndex) -> BTreeMap<String, ModeStats> {
+    let mut modules: BTreeMap<String, ModeStats> = BTreeMap::new();
+
+    for function in &index.functions {
+        let stats = modules.entry(function.module.clone()).or_default();
+        let lines = function.end_line + 1 - function.start_line;
+        match function.mode.as_str() {
+            "spec" => {
+                stats.spec_fns += 1;
+                stats.spec_lines += lines;
+            }
+            "proof" => {
+                stats.proof_fns += 1;
+                stats.proof_lines += lines;
+            }
+            _ => {
+                stats.exec_fns += 1;
+                stats.exec_lines += lines;
+            }
+        }
+    }
+
+    for block in &index.proof_blocks {
+        let stats = modules.entry(block.module.clone()).or_default();
+        stats.proof_block_lines += block.lines();
+        if block.fn_mode.as_deref() == Some("exec") {
+            stats.ghost_lines_in_exec += block.lines();
+        }
+    }
+
+    for clause in &index.spec_clauses {
+        let stats = modules.entry(clause.module.clone()).or_default();
+        stats.spec_clause_lines += clause.lines();
+        if clause.fn_mode.as_deref() == Some("exec") {
+            stats.ghost_lines_in_exec += clause.lines();
+        }
+    }
+
+    for trusted in &index.trusted {
+        modules.entry(trusted.module.clone()).or_default().trusted_items += 1;
+    }
+
+    modules
+}
+
+/// Compute a row per file, a row per inline module, and a final total row
+pub fn compute(indexes: &[(PathBuf, VerusIndex)]) -> Vec<StatsRow> {
+    let mut rows = Vec::new();
+    let mut total = ModeStats::default();
+
+    for (file, index) in indexes {
+        let file = file.display().to_string();
+        let modules = module_stats(index);
+
+        let mut file_stats = ModeStats::default();
+        for stats in modules.values() {
+            file_stats.add(stats);
+        }
+        total.add(&file_stats);
+
+        rows.push(StatsRow {
+            file: file.clone(),
+            module: String::new(),
+            stats: file_stats,
+        });
+        for (module, stats) in modules {
+            if !module.is_empty() {
+                rows.push(StatsRow {
+                    file: file.clone(),
+                    module,
+                    stats,
+                });
+            }
+        }
+    }
+
+    rows.push(StatsRow {
+        file: "TOTAL".to_string(),
+        module: String::new(),
+        stats: total,
+    });
+    rows
+}
+
+pub fn write_csv(out: &mut impl Write, rows: &[StatsRow]) -> Result<()> {
+    writeln!(
+        out,
+        "file,module,spec_fns,proof_fns,exec_fns,spec_lines,proof_lines,exec_lines,proof_block_lines,spec_clause_lines,trusted_items,ghost_exec_ratio"
+    )?;
+    for row in rows {
+        let s = &row.stats;
+        // Empty when there is no exec code, where the table shows `-`
+        let ratio = s.ghost_ratio().map(|ratio| format!("{:.2}", ratio)).unwrap_or_default();
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{},{},{},{},{},{}",
+            csv_field(&row.file), csv_field(&row.module),
+            s.spec_fns, s.proof_fns, s.exec_fns,
+            s.spec_lines, s.proof_lines, s.exec_lines,
+            s.proof_block_lines, s.spec_clause_lines, s.trusted_items, ratio
+        )?;
+    }
+    Ok(())
+}
+
+/// A CSV field quoted as RFC 4180 requires when it holds a comma, a quote or a line break
+fn csv_field(text: &str) -> Cow<'_, str> {
+    if text.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", text.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+pub fn write_table(out: &mut impl Write, rows: &[StatsRow]) -> Result<()> {
+    let labels: Vec<String> = rows
+        .iter()
+        .map(|row| {
+            if row.module.is_empty() {
+                row.file.clone()
+            } else {
+                format!("  mod {}", row.module)
+            }
+        })
+        .collect();
+    let width = labels.iter().map(|label| label.len()).max().unwrap_or(0).max(4);
+
+    writeln!(
+        out,
+        "{:<width$} {:>5} {:>5} {:>5} {:>10} {:>11} {:>10} {:>11} {:>12} {:>7} {:>10}",
+        "FILE", "spec", "proof", "exec",
+        "spec-lines", "proof-lines", "exec-lines",
+        "proof-block", "spec-clauses", "trusted", "ghost/exec",
+        width = width
+    )?;
+    for (label, row) in labels.iter().zip(rows) {
+        let s = &row.stats;
+        let ratio = s
+            .ghost_ratio()
+            .map(|ratio| format!("{:.2}", ratio))
+            .unwrap_or_else(|| "-".to_string());
+        writeln!(
+            out,
+            "{:<width$} {:>5} {:>5} {:>5} {:>10} {:>11} {:>10} {:>11} {:>12} {:>7} {:>10}",
+            label, s.spec_fns, s.proof_fns, s.exec_fns,
+            s.spec_lines, s.proof_lines, s.exec_lines,
+            s.proof_block_lines, s.spec_clause_lines, s.trusted_items, ratio,
+            width = width
+        )?;
+    }
+    Ok(())
+}
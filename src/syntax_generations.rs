@@ -0,0 +1,121 @@
+use proc_macro2::{Group, Ident, TokenStream, TokenTree};
+use std::ops::Range;
+
+/// Verus syntax newer than the bundled verus_syn, which a file can be
+/// parsed without by removing or replacing a token. Only constructs that verus_syn
+/// rejects belong here: `spec(checked)` and `broadcast` items parse fine.
+/// Syntax older than the bundled verus_syn is not detected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntaxChange {
+    /// `uninterp spec fn`
+    Uninterp,
+    /// `axiom fn`, a proof fn without a body to check
+    Axiom,
+}
+
+impl SyntaxChange {
+    const ALL: [SyntaxChange; 2] = [SyntaxChange::Uninterp, SyntaxChange::Axiom];
+
+    /// How the construct looks in source
+    pub fn construct(self) -> &'static str {
+        match self {
+            SyntaxChange::Uninterp => "uninterp spec fn",
+            SyntaxChange::Axiom => "axiom fn",
+        }
+    }
+
+    /// The first Verus releases that accept the construct, by the year and
+    /// month of their versions (releases are numbered `0.YYYY.MM.DD.commit`)
+    pub fn first_release(self) -> &'static str {
+        match self {
+            SyntaxChange::Uninterp => "0.2025.02",
+            SyntaxChange::Axiom => "0.2025.06",
+        }
+    }
+
+    /// The tokens to replace if the construct starts at `tokens[index]`,
+    /// and the identifier that takes their place, if any. `axiom fn`
+    /// becomes `proof fn`, so the fn keeps its mode.
+    fn occurrence(self, tokens: &[TokenTree], index: usize) -> Option<(Range<usize>, Option<&'static str>)> {
+        let ident_is = |offset: usize, words: &[&str]| {
+            matches!(tokens.get(index + offset), Some(TokenTree::Ident(ident)) if words.iter().any(|word| ident == word))
+        };
+        match self {
+            SyntaxChange::Uninterp if ident_is(0, &["uninterp"]) && ident_is(1, &["spec"]) => {
+                Some((index..index + 1, None))
+            }
+            SyntaxChange::Axiom if ident_is(0, &["axiom"]) && ident_is(1, &["fn"]) => {
+                Some((index..index + 1, Some("proof")))
+            }
+            SyntaxChange::Axiom if ident_is(0, &["axiom"]) && ident_is(1, &["proof"]) => {
+                Some((index..index + 1, None))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Parse `stream` with verus_syn after removing the syntax of other Verus
+/// generations. Returns `None` if it contains no such syntax or still does
+/// not parse, and otherwise the parsed value with the changes it needed.
+pub fn parse_tolerant<T: verus_syn::parse::Parse>(stream: TokenStream) -> Option<(T, Vec<SyntaxChange>)> {
+    let tokens: Vec<TokenTree> = stream.into_iter().collect();
+    let mut needed: Vec<SyntaxChange> = SyntaxChange::ALL
+        .into_iter()
+        .filter(|change| occurs(*change, &tokens))
+        .collect();
+    if needed.is_empty() {
+        return None;
+    }
+    verus_syn::parse2::<T>(rewrite(&tokens, &needed)).ok()?;
+
+    // Keep only the changes the parse depends on, so that syntax the bundled
+    // verus_syn does understand stays in the tree
+    for change in needed.clone() {
+        let fewer: Vec<SyntaxChange> = needed.iter().copied().filter(|other| *other != change).collect();
+        if verus_syn::parse2::<T>(rewrite(&tokens, &fewer)).is_ok() {
+            needed = fewer;
+        }
+    }
+    let parsed = verus_syn::parse2::<T>(rewrite(&tokens, &needed)).ok()?;
+    Some((parsed, needed))
+}
+
+/// `parse_tolerant` for a whole source file
+pub fn parse_file_tolerant(content: &str) -> Option<(verus_syn::File, Vec<SyntaxChange>)> {
+    parse_tolerant(content.parse().ok()?)
+}
+
+fn occurs(change: SyntaxChange, tokens: &[TokenTree]) -> bool {
+    tokens.iter().enumerate().any(|(index, token)| {
+        change.occurrence(tokens, index).is_some()
+            || matches!(token, TokenTree::Group(group) if occurs(change, &group.stream().into_iter().collect::<Vec<_>>()))
+    })
+}
+
+/// `tokens` with the occurrences of `changes` removed or replaced, at any
+/// depth. Spans are kept, so positions still refer to the original file.
+fn rewrite(tokens: &[TokenTree], changes: &[SyntaxChange]) -> TokenStream {
+    let mut rewritten = Vec::with_capacity(tokens.len());
+    let mut index = 0;
+    while index < tokens.len() {
+        if let Some((replaced, replacement)) = changes.iter().find_map(|change| change.occurrence(tokens, index)) {
+            if let Some(word) = replacement {
+                rewritten.push(TokenTree::Ident(Ident::new(word, tokens[replaced.start].span())));
+            }
+            index = replaced.end;
+            continue;
+        }
+        match &tokens[index] {
+            TokenTree::Group(group) => {
+                let inner: Vec<TokenTree> = group.stream().into_iter().collect();
+                let mut new_group = Group::new(group.delimiter(), rewrite(&inner, changes));
+                new_group.set_span(group.span());
+                rewritten.push(TokenTree::Group(new_group));
+            }
+            token => rewritten.push(token.clone()),
+        }
+        index += 1;
+    }
+    rewritten.into_iter().collect()
+}
@@ -0,0 +1,80 @@
+use anyhow::{bail, Context, Result};
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Standard library crates tagged by `--sysroot-std`
+pub const STD_CRATES: [&str; 3] = ["core", "alloc", "std"];
+
+/// The `rust-src` component of the active toolchain
+#[derive(Debug)]
+pub struct StdSources {
+    /// `<sysroot>/lib/rustlib/src/rust/library`
+    pub library_dir: PathBuf,
+    /// Identifies the toolchain, so each one gets its own cached tags file
+    pub toolchain: String,
+}
+
+/// Ask the project's toolchain for its sysroot. `rustc` is run from
+/// `project_dir`, so rustup honors the project's `rust-toolchain.toml`;
+/// `$RUSTC` overrides the compiler as it does for Cargo.
+pub fn locate_std(project_dir: &Path) -> Result<StdSources> {
+    let sysroot = PathBuf::from(rustc_output(&["--print", "sysroot"], project_dir)?.trim());
+    let library_dir = sysroot.join("lib/rustlib/src/rust/library");
+    if !library_dir.join("core").is_dir() {
+        bail!(
+            "rust-src not found under {} (run `rustup component add rust-src`)",
+            sysroot.display()
+        );
+    }
+
+    // `rustc -vV` prints `release: 1.80.0` and `commit-hash: <sha>`
+    let version = rustc_output(&["-vV"], project_dir)?;
+    let field = |key: &str| {
+        version
+            .lines()
+            .find_map(|line| line.strip_prefix(key))
+            .map(str::trim)
+            .filter(|value| !value.is_empty() && *value != "unknown")
+    };
+    let toolchain = match (field("release:"), field("commit-hash:")) {
+        (Some(release), Some(hash)) => format!("{}-{}", release, &hash[..hash.len().min(12)]),
+        (Some(release), None) => release.to_string(),
+        _ => sysroot
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "unknown".to_string()),
+    };
+
+    Ok(StdSources { library_dir, toolchain })
+}
+
+/// Cached tags file for a toolchain's standard library, tagged with the
+/// `--sort` mode, which decides the order of the tags in it
+pub fn std_cache_path(sources: &StdSources, sort: u8) -> Result<PathBuf> {
+    let cache_dir = env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))
+        .context("Cannot determine a cache directory (set XDG_CACHE_HOME or HOME)")?;
+    Ok(cache_dir
+        .join("verus-etags")
+        .join(format!("TAGS.std-{}-s{}", sources.toolchain, sort)))
+}
+
+fn rustc_output(args: &[&str], dir: &Path) -> Result<String> {
+    let rustc = env::var_os("RUSTC").unwrap_or_else(|| "rustc".into());
+    let output = Command::new(&rustc)
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .with_context(|| format!("Failed to run {}", rustc.to_string_lossy()))?;
+    if !output.status.success() {
+        bail!(
+            "{} {} failed: {}",
+            rustc.to_string_lossy(),
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
@@ -0,0 +1,44 @@
+/// An item and the items nested inside it
+#[derive(Debug)]
+pub struct Node<T> {
+    pub item: T,
+    pub children: Vec<Node<T>>,
+}
+
+/// Nest `(start_byte, end_byte, item)` entries by containment: an entry is a
+/// child of the innermost entry whose byte range strictly encloses its own.
+/// Bytes rather than lines, so items sharing a line, like the variants of a
+/// one-line enum, stay siblings. Used to turn the flat tags of a file into
+/// document symbols and outlines.
+pub fn nest<T>(mut entries: Vec<(usize, usize, T)>) -> Vec<Node<T>> {
+    // Outer items first when two start at the same byte
+    entries.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)));
+
+    let mut roots = Vec::new();
+    let mut open: Vec<((usize, usize), Node<T>)> = Vec::new();
+    for (start, end, item) in entries {
+        while open.last().is_some_and(|(range, _)| !encloses(*range, (start, end))) {
+            close(&mut open, &mut roots);
+        }
+        open.push(((start, end), Node { item, children: Vec::new() }));
+    }
+    while !open.is_empty() {
+        close(&mut open, &mut roots);
+    }
+    roots
+}
+
+/// Whether `outer` holds `inner` and is larger than it
+fn encloses(outer: (usize, usize), inner: (usize, usize)) -> bool {
+    outer.0 <= inner.0 && inner.1 <= outer.1 && outer != inner
+}
+
+/// Move the innermost open node into its parent, or into `roots`
+fn close<T>(open: &mut Vec<((usize, usize), Node<T>)>, roots: &mut Vec<Node<T>>) {
+    if let Some((_, node)) = open.pop() {
+        match open.last_mut() {
+            Some((_, parent)) => parent.children.push(node),
+            None => roots.push(node),
+        }
+    }
+}
@@ -1,26 +1,156 @@
 use verus_syn::{self as syn, *};
 use syn::visit::Visit;
-use proc_macro2::Span;
+use syn::spanned::Spanned;
+use proc_macro2::{LineColumn, Span};
+
+use crate::recovery::VerusBodies;
+use crate::verus_index::{self, VerusIndex};
+
+/// Check if a macro invocation is verus!, verus_!, or verus_impl!
+pub fn is_verus_macro(mac: &Macro) -> bool {
+    let ident = mac.path.segments.last().map(|seg| &seg.ident);
+    ident.map(|id| {
+        id == "verus" || id == "verus_" || id == "verus_impl"
+    }).unwrap_or(false)
+}
+
+/// What kind of definition a tag points at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagKind {
+    Function,
+    Struct,
+    Enum,
+    Variant,
+    Trait,
+    Impl,
+    Const,
+    Static,
+    Type,
+    Module,
+    Macro,
+    BroadcastGroup,
+    AssumeSpecification,
+    /// `group::member` entries of broadcast groups
+    BroadcastMember,
+    BroadcastUse,
+    Trigger,
+}
+
+impl TagKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TagKind::Function => "function",
+            TagKind::Struct => "struct",
+            TagKind::Enum => "enum",
+            TagKind::Variant => "variant",
+            TagKind::Trait => "trait",
+            TagKind::Impl => "impl",
+            TagKind::Const => "const",
+            TagKind::Static => "static",
+            TagKind::Type => "type",
+            TagKind::Module => "module",
+            TagKind::Macro => "macro",
+            TagKind::BroadcastGroup => "broadcast_group",
+            TagKind::AssumeSpecification => "assume_specification",
+            TagKind::BroadcastMember => "broadcast_member",
+            TagKind::BroadcastUse => "broadcast_use",
+            TagKind::Trigger => "trigger",
+        }
+    }
+
+    /// The LSP `SymbolKind` number used for this kind of tag
+    pub fn lsp_symbol_kind(self) -> u32 {
+        match self {
+            TagKind::Module => 2,
+            TagKind::BroadcastGroup => 3,
+            TagKind::Impl => 5,
+            TagKind::Enum => 10,
+            TagKind::Trait => 11,
+            TagKind::Function | TagKind::Macro | TagKind::AssumeSpecification => 12,
+            TagKind::Static => 13,
+            TagKind::Const => 14,
+            TagKind::BroadcastUse | TagKind::Trigger => 20,
+            TagKind::Variant | TagKind::BroadcastMember => 22,
+            TagKind::Struct => 23,
+            TagKind::Type => 26,
+        }
+    }
+
+    /// The kind of definition introduced by `keyword` (`fn`, `struct`, ...)
+    pub fn from_keyword(keyword: &str) -> Option<Self> {
+        match keyword {
+            "fn" => Some(TagKind::Function),
+            "struct" => Some(TagKind::Struct),
+            "enum" => Some(TagKind::Enum),
+            "trait" => Some(TagKind::Trait),
+            _ => None,
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct Tag {
     pub name: String,
+    pub kind: TagKind,
     pub line: usize,
+    /// 1-based column of the name (of the line's code for tags without a name token)
+    pub column: usize,
     pub byte_offset: usize,
+    /// First line of the tagged item, including its attributes and doc comments
+    pub start_line: usize,
+    /// Byte offset of the start of the item
+    pub start_byte: usize,
+    /// Last line of the tagged item
+    pub end_line: usize,
+    /// Byte offset just past the end of the item, e.g. its closing brace
+    pub end_byte: usize,
     pub pattern: String,
+    /// Enclosing modules, types, traits and functions, joined with `::`
+    pub scope: Option<String>,
+    /// `spec`, `proof` or `exec` for functions parsed by verus_syn
+    pub mode: Option<&'static str>,
+    /// For impls, the trait and self type as written, generic arguments
+    /// included, e.g. `impl View for Seq<A>`
+    pub header: Option<String>,
+}
+
+impl Tag {
+    /// The identifier the tag defines, which starts at its column; `None`
+    /// for impls, specifications and the tags of broadcast groups and
+    /// triggers, which define no name of their own
+    pub fn identifier(&self) -> Option<&str> {
+        match self.kind {
+            TagKind::Impl
+            | TagKind::AssumeSpecification
+            | TagKind::BroadcastMember
+            | TagKind::BroadcastUse
+            | TagKind::Trigger => None,
+            // `Enum::Variant`
+            TagKind::Variant => self.name.rsplit("::").next(),
+            _ => Some(&self.name),
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct TagVisitor<'a> {
     source: &'a str,
+    // Byte offset of the start of each line, computed once per file
+    line_starts: Vec<usize>,
     tags: Vec<Tag>,
+    scope: Vec<String>,
 }
 
 impl<'a> TagVisitor<'a> {
     pub fn new(source: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(index, _)| index + 1))
+            .collect();
         TagVisitor {
             source,
+            line_starts,
             tags: Vec::new(),
+            scope: Vec::new(),
         }
     }
 
@@ -32,129 +162,131 @@ impl<'a> TagVisitor<'a> {
         self.tags
     }
 
-    /// Process verus! and verus_! macro invocations to extract spec/proof/exec functions
-    pub fn process_verus_macros(&mut self, file: &File) {
-        for item in &file.items {
-            if let Item::Macro(item_macro) = item {
-                // Check if this is a verus!, verus_!, or verus_impl! macro
-                let ident = item_macro.mac.path.segments.last().map(|seg| &seg.ident);
-                let is_verus_macro = ident.map(|id| {
-                    id == "verus" || id == "verus_" || id == "verus_impl"
-                }).unwrap_or(false);
-                
-                if is_verus_macro {
-                    // Try to parse the macro contents as a File
-                    if let Ok(inner_file) = syn::parse2::<File>(item_macro.mac.tokens.clone()) {
-                        // Manually process items from the verus! macro
-                        self.process_verus_items(&inner_file.items);
-                    }
-                }
+    /// Add `group::member` tags for broadcast group members and
+    /// `broadcast use path` tags for every broadcast use site
+    pub fn add_index_tags(&mut self, index: &VerusIndex) {
+        for group in &index.broadcast_groups {
+            for member in &group.members {
+                self.add_line_tag(format!("{}::{}", group.name, member.name), TagKind::BroadcastMember, member.line);
             }
         }
+        for used in &index.broadcast_uses {
+            self.add_line_tag(format!("broadcast use {}", used.path), TagKind::BroadcastUse, used.line);
+        }
     }
 
-    fn process_verus_items(&mut self, items: &[Item]) {
-        for item in items {
-            match item {
-                Item::Fn(item_fn) => {
-                    let name = item_fn.sig.ident.to_string();
-                    self.add_tag(name, item_fn.sig.ident.span());
-                }
-                Item::Struct(item_struct) => {
-                    let name = item_struct.ident.to_string();
-                    self.add_tag(name, item_struct.ident.span());
-                }
-                Item::Enum(item_enum) => {
-                    let name = item_enum.ident.to_string();
-                    self.add_tag(name.clone(), item_enum.ident.span());
-                    for variant in &item_enum.variants {
-                        let variant_name = format!("{}::{}", name, variant.ident);
-                        self.add_tag(variant_name, variant.ident.span());
-                    }
-                }
-                Item::Trait(item_trait) => {
-                    let name = item_trait.ident.to_string();
-                    self.add_tag(name, item_trait.ident.span());
-                }
-                Item::Impl(item_impl) => {
-                    if let Type::Path(type_path) = &*item_impl.self_ty {
-                        if let Some(segment) = type_path.path.segments.last() {
-                            let impl_name = if let Some((_, trait_path, _)) = &item_impl.trait_ {
-                                if let Some(trait_segment) = trait_path.segments.last() {
-                                    format!("impl {} for {}", trait_segment.ident, segment.ident)
-                                } else {
-                                    format!("impl {}", segment.ident)
-                                }
-                            } else {
-                                format!("impl {}", segment.ident)
-                            };
-                            self.add_tag(impl_name, segment.ident.span());
-                        }
-                    }
-                    
-                    // Process impl items
-                    for impl_item in &item_impl.items {
-                        if let ImplItem::Fn(impl_item_fn) = impl_item {
-                            let name = impl_item_fn.sig.ident.to_string();
-                            self.add_tag(name, impl_item_fn.sig.ident.span());
-                        }
+    /// Add `trigger:fn_name` tags for every indexed trigger term
+    pub fn add_trigger_tags(&mut self, index: &VerusIndex) {
+        for trigger in &index.triggers {
+            self.add_line_tag(format!("trigger:{}", trigger.function), TagKind::Trigger, trigger.line);
+        }
+    }
+
+    /// Process verus! and verus_! macro invocations to extract spec/proof/exec functions,
+    /// from their bodies as parsed in `bodies`. Body items are visited like
+    /// the items outside macros, so modules, traits and impls get their
+    /// nested items and scopes.
+    pub fn process_verus_macros(&mut self, file: &'a File, bodies: &'a VerusBodies) {
+        for item in &file.items {
+            if let Item::Macro(item_macro) = item {
+                if is_verus_macro(&item_macro.mac) {
+                    for body_item in bodies.items(&item_macro.mac) {
+                        self.visit_item(body_item);
                     }
                 }
-                Item::Const(item_const) => {
-                    let name = item_const.ident.to_string();
-                    self.add_tag(name, item_const.ident.span());
-                }
-                Item::Static(item_static) => {
-                    let name = item_static.ident.to_string();
-                    self.add_tag(name, item_static.ident.span());
-                }
-                Item::Type(item_type) => {
-                    let name = item_type.ident.to_string();
-                    self.add_tag(name, item_type.ident.span());
-                }
-                Item::Mod(item_mod) => {
-                    let name = item_mod.ident.to_string();
-                    self.add_tag(name, item_mod.ident.span());
-                }
-                Item::BroadcastGroup(item_bg) => {
-                    let name = item_bg.ident.to_string();
-                    self.add_tag(name, item_bg.ident.span());
-                }
-                _ => {}
             }
         }
     }
 
-    fn add_tag(&mut self, name: String, span: Span) {
+    /// Add a tag for the name at `span`, defined by an item spanning
+    /// `item_span`. Item spans start at the item's outer attributes, which
+    /// include its doc comments.
+    fn add_tag(&mut self, name: String, kind: TagKind, span: Span, item_span: Span) -> &mut Tag {
         let start = span.start();
-        let line = start.line;
+        let byte_offset = self.get_line_start_offset(start.line);
+        let pattern = self.extract_pattern(byte_offset, &name);
+        let (item_start, item_end) = (item_span.start(), item_span.end());
+
+        self.tags.push(Tag {
+            name,
+            kind,
+            line: start.line,
+            column: start.column + 1,
+            byte_offset,
+            start_line: item_start.line.min(start.line),
+            start_byte: self.byte_at(item_start).min(self.byte_at(start)),
+            end_line: item_end.line.max(start.line),
+            end_byte: self.byte_at(item_end),
+            pattern,
+            scope: self.current_scope(),
+            mode: None,
+            header: None,
+        });
+        self.tags.last_mut().expect("a tag was just added")
+    }
+
+    /// Add a tag for a location recorded elsewhere (e.g. in a `VerusIndex`)
+    pub fn add_line_tag(&mut self, name: String, kind: TagKind, line: usize) {
         let byte_offset = self.get_line_start_offset(line);
         let pattern = self.extract_pattern(byte_offset, &name);
-        
+        let column = pattern.chars().take_while(|c| c.is_whitespace()).count() + 1;
+        // Without an item span, the item is taken to be the code on the line
+        let line_end = self.source[byte_offset..].find('\n').map_or(self.source.len(), |end| byte_offset + end);
+
         self.tags.push(Tag {
             name,
+            kind,
             line,
+            column,
             byte_offset,
+            start_line: line,
+            start_byte: byte_offset + column - 1,
+            end_line: line,
+            end_byte: line_end,
             pattern,
+            scope: None,
+            mode: None,
+            header: None,
         });
     }
 
-    fn get_line_start_offset(&self, line_num: usize) -> usize {
-        if line_num == 1 {
-            return 0;
-        }
+    /// Run `visit` with `name` as the innermost enclosing scope
+    fn within(&mut self, name: String, visit: impl FnOnce(&mut Self)) {
+        self.scope.push(name);
+        visit(self);
+        self.scope.pop();
+    }
 
-        let mut current_line = 1;
-        for (idx, ch) in self.source.char_indices() {
-            if ch == '\n' {
-                current_line += 1;
-                if current_line == line_num {
-                    return idx + 1;
-                }
-            }
-        }
+    fn current_scope(&self) -> Option<String> {
+        (!self.scope.is_empty()).then(|| self.scope.join("::"))
+    }
 
-        0
+    fn get_line_start_offset(&self, line_num: usize) -> usize {
+        line_num
+            .checked_sub(1)
+            .and_then(|index| self.line_starts.get(index))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Byte offset of a span location, whose column counts characters
+    fn byte_at(&self, location: LineColumn) -> usize {
+        let line_start = self.get_line_start_offset(location.line);
+        let line = &self.source[line_start..];
+        line_start + line.char_indices().nth(location.column).map_or(line.len(), |(index, _)| index)
+    }
+
+    /// `impl Trait for Type`, or `impl Type`, with the trait and type as
+    /// written and their whitespace collapsed
+    fn impl_header(&self, trait_span: Option<Span>, self_ty_span: Span) -> String {
+        let written = |span: Span| {
+            let text = &self.source[self.byte_at(span.start())..self.byte_at(span.end())];
+            text.split_whitespace().collect::<Vec<_>>().join(" ")
+        };
+        match trait_span {
+            Some(trait_span) => format!("impl {} for {}", written(trait_span), written(self_ty_span)),
+            None => format!("impl {}", written(self_ty_span)),
+        }
     }
 
     fn extract_pattern(&self, byte_offset: usize, _name: &str) -> String {
@@ -182,41 +314,45 @@ impl<'a> TagVisitor<'a> {
 impl<'a> Visit<'a> for TagVisitor<'a> {
     fn visit_item_fn(&mut self, node: &'a ItemFn) {
         let name = node.sig.ident.to_string();
-        self.add_tag(name, node.sig.ident.span());
+        self.add_tag(name.clone(), TagKind::Function, node.sig.ident.span(), node.span()).mode =
+            Some(verus_index::fn_mode(&node.sig));
         
         // Continue visiting nested items
-        syn::visit::visit_item_fn(self, node);
+        self.within(name, |visitor| syn::visit::visit_item_fn(visitor, node));
     }
 
     fn visit_item_struct(&mut self, node: &'a ItemStruct) {
         let name = node.ident.to_string();
-        self.add_tag(name, node.ident.span());
+        self.add_tag(name, TagKind::Struct, node.ident.span(), node.span());
         
         syn::visit::visit_item_struct(self, node);
     }
 
     fn visit_item_enum(&mut self, node: &'a ItemEnum) {
         let name = node.ident.to_string();
-        self.add_tag(name.clone(), node.ident.span());
+        self.add_tag(name.clone(), TagKind::Enum, node.ident.span(), node.span());
         
         // Add enum variants
-        for variant in &node.variants {
-            let variant_name = format!("{}::{}", name, variant.ident);
-            self.add_tag(variant_name, variant.ident.span());
-        }
+        self.within(name.clone(), |visitor| {
+            for variant in &node.variants {
+                let variant_name = format!("{}::{}", name, variant.ident);
+                visitor.add_tag(variant_name, TagKind::Variant, variant.ident.span(), variant.span());
+            }
+        });
         
         syn::visit::visit_item_enum(self, node);
     }
 
     fn visit_item_trait(&mut self, node: &'a ItemTrait) {
         let name = node.ident.to_string();
-        self.add_tag(name, node.ident.span());
+        self.add_tag(name.clone(), TagKind::Trait, node.ident.span(), node.span());
         
-        syn::visit::visit_item_trait(self, node);
+        self.within(name, |visitor| syn::visit::visit_item_trait(visitor, node));
     }
 
     fn visit_item_impl(&mut self, node: &'a ItemImpl) {
         // For impl blocks, we tag the type being implemented
+        let mut self_name = None;
         if let Type::Path(type_path) = &*node.self_ty {
             if let Some(segment) = type_path.path.segments.last() {
                 let impl_name = if let Some((_, trait_path, _)) = &node.trait_ {
@@ -231,59 +367,65 @@ impl<'a> Visit<'a> for TagVisitor<'a> {
                     format!("impl {}", segment.ident)
                 };
                 
-                self.add_tag(impl_name, segment.ident.span());
+                let trait_span = node.trait_.as_ref().map(|(_, trait_path, _)| trait_path.span());
+                let header = self.impl_header(trait_span, node.self_ty.span());
+                self.add_tag(impl_name, TagKind::Impl, segment.ident.span(), node.span()).header = Some(header);
+                self_name = Some(segment.ident.to_string());
             }
         }
         
-        syn::visit::visit_item_impl(self, node);
+        let scope = self_name.unwrap_or_else(|| "impl".to_string());
+        self.within(scope, |visitor| syn::visit::visit_item_impl(visitor, node));
     }
 
     fn visit_impl_item_fn(&mut self, node: &'a ImplItemFn) {
         let name = node.sig.ident.to_string();
-        self.add_tag(name, node.sig.ident.span());
+        self.add_tag(name.clone(), TagKind::Function, node.sig.ident.span(), node.span()).mode =
+            Some(verus_index::fn_mode(&node.sig));
         
-        syn::visit::visit_impl_item_fn(self, node);
+        self.within(name, |visitor| syn::visit::visit_impl_item_fn(visitor, node));
     }
 
     fn visit_trait_item_fn(&mut self, node: &'a TraitItemFn) {
         let name = node.sig.ident.to_string();
-        self.add_tag(name, node.sig.ident.span());
+        self.add_tag(name.clone(), TagKind::Function, node.sig.ident.span(), node.span()).mode =
+            Some(verus_index::fn_mode(&node.sig));
         
-        syn::visit::visit_trait_item_fn(self, node);
+        self.within(name, |visitor| syn::visit::visit_trait_item_fn(visitor, node));
     }
 
     fn visit_item_type(&mut self, node: &'a ItemType) {
         let name = node.ident.to_string();
-        self.add_tag(name, node.ident.span());
+        self.add_tag(name, TagKind::Type, node.ident.span(), node.span());
         
         syn::visit::visit_item_type(self, node);
     }
 
     fn visit_item_const(&mut self, node: &'a ItemConst) {
         let name = node.ident.to_string();
-        self.add_tag(name, node.ident.span());
+        self.add_tag(name, TagKind::Const, node.ident.span(), node.span());
         
         syn::visit::visit_item_const(self, node);
     }
 
     fn visit_item_static(&mut self, node: &'a ItemStatic) {
         let name = node.ident.to_string();
-        self.add_tag(name, node.ident.span());
+        self.add_tag(name, TagKind::Static, node.ident.span(), node.span());
         
         syn::visit::visit_item_static(self, node);
     }
 
     fn visit_item_mod(&mut self, node: &'a ItemMod) {
         let name = node.ident.to_string();
-        self.add_tag(name, node.ident.span());
+        self.add_tag(name.clone(), TagKind::Module, node.ident.span(), node.span());
         
-        syn::visit::visit_item_mod(self, node);
+        self.within(name, |visitor| syn::visit::visit_item_mod(visitor, node));
     }
 
     fn visit_item_macro(&mut self, node: &'a ItemMacro) {
         if let Some(ident) = &node.ident {
             let name = ident.to_string();
-            self.add_tag(name, ident.span());
+            self.add_tag(name, TagKind::Macro, ident.span(), node.span());
         }
         
         syn::visit::visit_item_macro(self, node);
@@ -291,7 +433,7 @@ impl<'a> Visit<'a> for TagVisitor<'a> {
 
     fn visit_item_broadcast_group(&mut self, node: &'a ItemBroadcastGroup) {
         let name = node.ident.to_string();
-        self.add_tag(name, node.ident.span());
+        self.add_tag(name, TagKind::BroadcastGroup, node.ident.span(), node.span());
         
         syn::visit::visit_item_broadcast_group(self, node);
     }
@@ -300,7 +442,7 @@ impl<'a> Visit<'a> for TagVisitor<'a> {
         // Extract the function name from the path
         if let Some(segment) = node.path.segments.last() {
             let name = format!("assume_specification {}", segment.ident);
-            self.add_tag(name, segment.ident.span());
+            self.add_tag(name, TagKind::AssumeSpecification, segment.ident.span(), node.span());
         }
         
         syn::visit::visit_assume_specification(self, node);
@@ -318,35 +460,38 @@ impl<'a> TagVisitor<'a> {
 impl<'a> ::syn::visit::Visit<'a> for TagVisitor<'a> {
     fn visit_item_fn(&mut self, node: &'a ::syn::ItemFn) {
         let name = node.sig.ident.to_string();
-        self.add_tag(name, node.sig.ident.span());
-        ::syn::visit::visit_item_fn(self, node);
+        self.add_tag(name.clone(), TagKind::Function, node.sig.ident.span(), node.span());
+        self.within(name, |visitor| ::syn::visit::visit_item_fn(visitor, node));
     }
 
     fn visit_item_struct(&mut self, node: &'a ::syn::ItemStruct) {
         let name = node.ident.to_string();
-        self.add_tag(name, node.ident.span());
+        self.add_tag(name, TagKind::Struct, node.ident.span(), node.span());
         ::syn::visit::visit_item_struct(self, node);
     }
 
     fn visit_item_enum(&mut self, node: &'a ::syn::ItemEnum) {
         let name = node.ident.to_string();
-        self.add_tag(name.clone(), node.ident.span());
+        self.add_tag(name.clone(), TagKind::Enum, node.ident.span(), node.span());
         
-        for variant in &node.variants {
-            let variant_name = format!("{}::{}", name, variant.ident);
-            self.add_tag(variant_name, variant.ident.span());
-        }
+        self.within(name.clone(), |visitor| {
+            for variant in &node.variants {
+                let variant_name = format!("{}::{}", name, variant.ident);
+                visitor.add_tag(variant_name, TagKind::Variant, variant.ident.span(), variant.span());
+            }
+        });
         
         ::syn::visit::visit_item_enum(self, node);
     }
 
     fn visit_item_trait(&mut self, node: &'a ::syn::ItemTrait) {
         let name = node.ident.to_string();
-        self.add_tag(name, node.ident.span());
-        ::syn::visit::visit_item_trait(self, node);
+        self.add_tag(name.clone(), TagKind::Trait, node.ident.span(), node.span());
+        self.within(name, |visitor| ::syn::visit::visit_item_trait(visitor, node));
     }
 
     fn visit_item_impl(&mut self, node: &'a ::syn::ItemImpl) {
+        let mut self_name = None;
         if let ::syn::Type::Path(type_path) = &*node.self_ty {
             if let Some(segment) = type_path.path.segments.last() {
                 let impl_name = if let Some((_, trait_path, _)) = &node.trait_ {
@@ -359,55 +504,58 @@ impl<'a> ::syn::visit::Visit<'a> for TagVisitor<'a> {
                     format!("impl {}", segment.ident)
                 };
                 
-                self.add_tag(impl_name, segment.ident.span());
+                let trait_span = node.trait_.as_ref().map(|(_, trait_path, _)| trait_path.span());
+                let header = self.impl_header(trait_span, node.self_ty.span());
+                self.add_tag(impl_name, TagKind::Impl, segment.ident.span(), node.span()).header = Some(header);
+                self_name = Some(segment.ident.to_string());
             }
         }
         
-        ::syn::visit::visit_item_impl(self, node);
+        let scope = self_name.unwrap_or_else(|| "impl".to_string());
+        self.within(scope, |visitor| ::syn::visit::visit_item_impl(visitor, node));
     }
 
     fn visit_impl_item_fn(&mut self, node: &'a ::syn::ImplItemFn) {
         let name = node.sig.ident.to_string();
-        self.add_tag(name, node.sig.ident.span());
-        ::syn::visit::visit_impl_item_fn(self, node);
+        self.add_tag(name.clone(), TagKind::Function, node.sig.ident.span(), node.span());
+        self.within(name, |visitor| ::syn::visit::visit_impl_item_fn(visitor, node));
     }
 
     fn visit_trait_item_fn(&mut self, node: &'a ::syn::TraitItemFn) {
         let name = node.sig.ident.to_string();
-        self.add_tag(name, node.sig.ident.span());
-        ::syn::visit::visit_trait_item_fn(self, node);
+        self.add_tag(name.clone(), TagKind::Function, node.sig.ident.span(), node.span());
+        self.within(name, |visitor| ::syn::visit::visit_trait_item_fn(visitor, node));
     }
 
     fn visit_item_type(&mut self, node: &'a ::syn::ItemType) {
         let name = node.ident.to_string();
-        self.add_tag(name, node.ident.span());
+        self.add_tag(name, TagKind::Type, node.ident.span(), node.span());
         ::syn::visit::visit_item_type(self, node);
     }
 
     fn visit_item_const(&mut self, node: &'a ::syn::ItemConst) {
         let name = node.ident.to_string();
-        self.add_tag(name, node.ident.span());
+        self.add_tag(name, TagKind::Const, node.ident.span(), node.span());
         ::syn::visit::visit_item_const(self, node);
     }
 
     fn visit_item_static(&mut self, node: &'a ::syn::ItemStatic) {
         let name = node.ident.to_string();
-        self.add_tag(name, node.ident.span());
+        self.add_tag(name, TagKind::Static, node.ident.span(), node.span());
         ::syn::visit::visit_item_static(self, node);
     }
 
     fn visit_item_mod(&mut self, node: &'a ::syn::ItemMod) {
         let name = node.ident.to_string();
-        self.add_tag(name, node.ident.span());
-        ::syn::visit::visit_item_mod(self, node);
+        self.add_tag(name.clone(), TagKind::Module, node.ident.span(), node.span());
+        self.within(name, |visitor| ::syn::visit::visit_item_mod(visitor, node));
     }
 
     fn visit_item_macro(&mut self, node: &'a ::syn::ItemMacro) {
         if let Some(ident) = &node.ident {
             let name = ident.to_string();
-            self.add_tag(name, ident.span());
+            self.add_tag(name, TagKind::Macro, ident.span(), node.span());
         }
         ::syn::visit::visit_item_macro(self, node);
     }
 }
-
@@ -0,0 +1,835 @@
+use verus_syn::{self as syn, *};
+use syn::visit::Visit;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use proc_macro2::Span;
+use quote::ToTokens;
+use serde::Serialize;
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+
+use crate::recovery::VerusBodies;
+use crate::tag_visitor::is_verus_macro;
+
+/// A member of a `broadcast group`
+#[derive(Debug, Clone, Serialize)]
+pub struct BroadcastMember {
+    pub path: String,
+    pub name: String,
+    pub line: usize,
+}
+
+/// A `broadcast group` definition and its member list
+#[derive(Debug, Clone, Serialize)]
+pub struct BroadcastGroup {
+    pub name: String,
+    pub line: usize,
+    pub members: Vec<BroadcastMember>,
+}
+
+/// A single path named by a `broadcast use` statement
+#[derive(Debug, Clone, Serialize)]
+pub struct BroadcastUse {
+    pub path: String,
+    pub name: String,
+    pub line: usize,
+    pub enclosing: Option<String>,
+}
+
+/// A quantifier trigger term, from `#[trigger]` or `#![trigger ...]`
+#[derive(Debug, Clone, Serialize)]
+pub struct Trigger {
+    /// Head function of the trigger term (e.g. `f` for `f(x, y)`)
+    pub function: String,
+    pub term: String,
+    pub line: usize,
+    /// `forall`, `exists` or `choose`; `None` for triggers on broadcast lemmas
+    pub quantifier: Option<String>,
+    pub enclosing: Option<String>,
+    pub enclosing_line: Option<usize>,
+}
+
+/// A function definition and its Verus mode (`spec`, `proof` or `exec`)
+#[derive(Debug, Clone, Serialize)]
+pub struct FunctionDef {
+    pub name: String,
+    pub mode: String,
+    pub line: usize,
+    /// First and last line of the whole item, including attributes and body
+    pub start_line: usize,
+    pub end_line: usize,
+    /// Path of the inline `mod` blocks enclosing the function within its file
+    pub module: String,
+}
+
+/// A range of lines within one inline module, e.g. a `proof { }` block
+#[derive(Debug, Clone, Serialize)]
+pub struct LineRange {
+    pub start_line: usize,
+    pub end_line: usize,
+    pub module: String,
+    /// Mode of the enclosing function, if any
+    pub fn_mode: Option<String>,
+}
+
+impl LineRange {
+    pub fn lines(&self) -> usize {
+        self.end_line + 1 - self.start_line
+    }
+}
+
+/// An item whose correctness is assumed rather than verified:
+/// `#[verifier::external_body]`, `#[verifier::external]`, or `assume_specification`
+#[derive(Debug, Clone, Serialize)]
+pub struct TrustedItem {
+    pub name: String,
+    pub kind: String,
+    pub line: usize,
+    pub module: String,
+}
+
+/// A call from one function to another
+#[derive(Debug, Clone, Serialize)]
+pub struct CallEdge {
+    pub caller: String,
+    pub caller_mode: String,
+    /// Callee as written at the call site (e.g. `Seq::empty`, `lemma_len`)
+    pub callee_path: String,
+    pub callee: String,
+    /// Where the call occurs: `exec` code, `proof` code, or a `spec`
+    /// (spec fn body, requires/ensures/invariant clause, assertion)
+    pub context: String,
+    pub line: usize,
+}
+
+/// A use of a name as an expression: a call, a method call, a `reveal`,
+/// a `broadcast use`, or a broadcast group member
+#[derive(Debug, Clone, Serialize)]
+pub struct Reference {
+    pub name: String,
+    pub line: usize,
+    /// 1-based column of the name
+    pub column: usize,
+    /// Innermost enclosing function, used to ignore self-recursion
+    pub enclosing_fn: Option<String>,
+}
+
+/// Verus-specific facts collected from one file, beyond plain definition tags
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct VerusIndex {
+    pub broadcast_groups: Vec<BroadcastGroup>,
+    pub broadcast_uses: Vec<BroadcastUse>,
+    pub triggers: Vec<Trigger>,
+    pub functions: Vec<FunctionDef>,
+    pub calls: Vec<CallEdge>,
+    pub references: Vec<Reference>,
+    pub proof_blocks: Vec<LineRange>,
+    pub spec_clauses: Vec<LineRange>,
+    pub trusted: Vec<TrustedItem>,
+}
+
+impl VerusIndex {
+    /// Walk a whole file, including the bodies of verus! macros, as parsed
+    /// in `bodies`
+    pub fn collect(file: &File, bodies: &VerusBodies) -> Self {
+        let mut visitor = IndexVisitor {
+            bodies,
+            index: VerusIndex::default(),
+            item_stack: Vec::new(),
+            quantifier_stack: Vec::new(),
+            fn_stack: Vec::new(),
+            context_stack: Vec::new(),
+            mod_stack: Vec::new(),
+        };
+        visitor.visit_file(file);
+        visitor.index
+    }
+}
+
+#[derive(Debug)]
+struct IndexVisitor<'b> {
+    bodies: &'b VerusBodies,
+    index: VerusIndex,
+    // Names and lines of the items enclosing the current position, outermost first
+    item_stack: Vec<(String, usize)>,
+    // Quantifiers enclosing the current position, innermost last
+    quantifier_stack: Vec<&'static str>,
+    // Enclosing functions and their modes, innermost last
+    fn_stack: Vec<(String, &'static str)>,
+    // Mode of the code at the current position, innermost last
+    context_stack: Vec<&'static str>,
+    // Inline modules enclosing the current position, outermost first
+    mod_stack: Vec<String>,
+}
+
+fn path_to_string(path: &Path) -> String {
+    path.segments
+        .iter()
+        .map(|seg| seg.ident.to_string())
+        .collect::<Vec<_>>()
+        .join("::")
+}
+
+fn path_last_name(path: &Path) -> String {
+    path.segments
+        .last()
+        .map(|seg| seg.ident.to_string())
+        .unwrap_or_default()
+}
+
+fn is_trigger_attr(attr: &Attribute) -> bool {
+    attr.path()
+        .segments
+        .last()
+        .map(|seg| seg.ident == "trigger")
+        .unwrap_or(false)
+}
+
+/// Names of `#[verifier::name]` and `#[verifier(name)]` attributes
+fn verifier_attr_names(attrs: &[Attribute]) -> Vec<String> {
+    let mut names = Vec::new();
+    for attr in attrs {
+        let path = attr.path();
+        let is_verifier = path
+            .segments
+            .first()
+            .map(|seg| seg.ident == "verifier")
+            .unwrap_or(false);
+        if !is_verifier {
+            continue;
+        }
+        if path.segments.len() > 1 {
+            names.extend(path.segments.last().map(|seg| seg.ident.to_string()));
+        } else if let Meta::List(_) = &attr.meta {
+            if let Ok(args) = attr.parse_args_with(Punctuated::<Ident, Token![,]>::parse_terminated) {
+                names.extend(args.iter().map(|arg| arg.to_string()));
+            }
+        }
+    }
+    names
+}
+
+fn trusted_kind(attrs: &[Attribute]) -> Option<&'static str> {
+    let names = verifier_attr_names(attrs);
+    if names.iter().any(|name| name == "external_body") {
+        Some("external_body")
+    } else if names.iter().any(|name| name == "external") {
+        Some("external")
+    } else {
+        None
+    }
+}
+
+fn line_range(span: Span) -> (usize, usize) {
+    (span.start().line, span.end().line)
+}
+
+/// `spec`, `proof` or `exec`
+pub fn fn_mode(sig: &Signature) -> &'static str {
+    match &sig.mode {
+        FnMode::Spec(_) => "spec",
+        FnMode::SpecChecked(_) => "spec",
+        FnMode::Proof(_) => "proof",
+        _ => "exec",
+    }
+}
+
+fn quantifier_kind(op: &UnOp) -> Option<&'static str> {
+    match op {
+        UnOp::Forall(_) => Some("forall"),
+        UnOp::Exists(_) => Some("exists"),
+        UnOp::Choose(_) => Some("choose"),
+        _ => None,
+    }
+}
+
+fn call_head(call: &ExprCall) -> Option<(String, usize)> {
+    match &*call.func {
+        Expr::Path(func) => func
+            .path
+            .segments
+            .last()
+            .map(|seg| (seg.ident.to_string(), seg.ident.span().start().line)),
+        _ => None,
+    }
+}
+
+fn method_call_head(call: &ExprMethodCall) -> Option<(String, usize)> {
+    Some((call.method.to_string(), call.method.span().start().line))
+}
+
+/// Name and line of the function at the head of a trigger term, if it has one
+fn trigger_head(expr: &Expr) -> Option<(String, usize)> {
+    match expr {
+        Expr::Call(call) => call_head(call),
+        Expr::MethodCall(call) => method_call_head(call),
+        Expr::Paren(paren) => trigger_head(&paren.expr),
+        Expr::Group(group) => trigger_head(&group.expr),
+        _ => None,
+    }
+}
+
+impl IndexVisitor<'_> {
+    fn enclosing(&self) -> Option<String> {
+        self.item_stack.last().map(|(name, _)| name.clone())
+    }
+
+    fn push_item(&mut self, ident: &Ident) {
+        self.item_stack.push((ident.to_string(), ident.span().start().line));
+    }
+
+    fn module_path(&self) -> String {
+        self.mod_stack.join("::")
+    }
+
+    fn module_line_range(&mut self, span: Span) -> LineRange {
+        let (start_line, end_line) = line_range(span);
+        LineRange {
+            start_line,
+            end_line,
+            module: self.module_path(),
+            fn_mode: self.fn_stack.last().map(|(_, mode)| mode.to_string()),
+        }
+    }
+
+    fn record_spec_clause(&mut self, span: Span) {
+        let range = self.module_line_range(span);
+        self.index.spec_clauses.push(range);
+    }
+
+    fn record_trusted(&mut self, attrs: &[Attribute], ident: &Ident) {
+        if let Some(kind) = trusted_kind(attrs) {
+            self.index.trusted.push(TrustedItem {
+                name: ident.to_string(),
+                kind: kind.to_string(),
+                line: ident.span().start().line,
+                module: self.module_path(),
+            });
+        }
+    }
+
+    fn enter_fn(&mut self, sig: &Signature, attrs: &[Attribute], span: Span) {
+        let mode = fn_mode(sig);
+        let (start_line, end_line) = line_range(span);
+        self.index.functions.push(FunctionDef {
+            name: sig.ident.to_string(),
+            mode: mode.to_string(),
+            line: sig.ident.span().start().line,
+            start_line,
+            end_line,
+            module: self.module_path(),
+        });
+        self.record_trusted(attrs, &sig.ident);
+        self.push_item(&sig.ident);
+        self.fn_stack.push((sig.ident.to_string(), mode));
+        self.context_stack.push(mode);
+    }
+
+    fn exit_fn(&mut self) {
+        self.context_stack.pop();
+        self.fn_stack.pop();
+        self.item_stack.pop();
+    }
+
+    // Visit a subtree whose code is in the given mode (e.g. a spec clause)
+    fn with_context(&mut self, context: &'static str, visit: impl FnOnce(&mut Self)) {
+        self.context_stack.push(context);
+        visit(self);
+        self.context_stack.pop();
+    }
+
+    fn record_call(&mut self, path: &Path) {
+        // Calls outside any function body (e.g. in const initializers) have no caller
+        let Some((caller, caller_mode)) = self.fn_stack.last() else {
+            return;
+        };
+        let Some(last) = path.segments.last() else {
+            return;
+        };
+        self.index.calls.push(CallEdge {
+            caller: caller.clone(),
+            caller_mode: caller_mode.to_string(),
+            callee_path: path_to_string(path),
+            callee: last.ident.to_string(),
+            context: self.context_stack.last().unwrap_or(caller_mode).to_string(),
+            line: last.ident.span().start().line,
+        });
+    }
+
+    fn record_reference(&mut self, ident: &Ident) {
+        self.index.references.push(Reference {
+            name: ident.to_string(),
+            line: ident.span().start().line,
+            column: ident.span().start().column + 1,
+            enclosing_fn: self.fn_stack.last().map(|(name, _)| name.clone()),
+        });
+    }
+
+    fn record_method_call(&mut self, method: &Ident) {
+        let Some((caller, caller_mode)) = self.fn_stack.last() else {
+            return;
+        };
+        self.index.calls.push(CallEdge {
+            caller: caller.clone(),
+            caller_mode: caller_mode.to_string(),
+            callee_path: method.to_string(),
+            callee: method.to_string(),
+            context: self.context_stack.last().unwrap_or(caller_mode).to_string(),
+            line: method.span().start().line,
+        });
+    }
+
+    // Terms without a function head (e.g. `a[i]`, `x + 1`) are not indexed
+    fn record_trigger(&mut self, term: &dyn ToTokens, head: Option<(String, usize)>) {
+        if let Some((function, line)) = head {
+            self.index.triggers.push(Trigger {
+                function,
+                term: term.to_token_stream().to_string(),
+                line,
+                quantifier: self.quantifier_stack.last().map(|kind| kind.to_string()),
+                enclosing: self.enclosing(),
+                enclosing_line: self.item_stack.last().map(|(_, line)| *line),
+            });
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for IndexVisitor<'_> {
+    fn visit_item_macro(&mut self, node: &'ast ItemMacro) {
+        if is_verus_macro(&node.mac) {
+            for item in self.bodies.items(&node.mac) {
+                self.visit_item(item);
+            }
+        }
+
+        syn::visit::visit_item_macro(self, node);
+    }
+
+    fn visit_item_fn(&mut self, node: &'ast ItemFn) {
+        self.enter_fn(&node.sig, &node.attrs, node.span());
+        syn::visit::visit_item_fn(self, node);
+        self.exit_fn();
+    }
+
+    fn visit_impl_item_fn(&mut self, node: &'ast ImplItemFn) {
+        self.enter_fn(&node.sig, &node.attrs, node.span());
+        syn::visit::visit_impl_item_fn(self, node);
+        self.exit_fn();
+    }
+
+    fn visit_trait_item_fn(&mut self, node: &'ast TraitItemFn) {
+        self.enter_fn(&node.sig, &node.attrs, node.span());
+        syn::visit::visit_trait_item_fn(self, node);
+        self.exit_fn();
+    }
+
+    fn visit_item_mod(&mut self, node: &'ast ItemMod) {
+        self.push_item(&node.ident);
+        self.mod_stack.push(node.ident.to_string());
+        syn::visit::visit_item_mod(self, node);
+        self.mod_stack.pop();
+        self.item_stack.pop();
+    }
+
+    fn visit_item_impl(&mut self, node: &'ast ItemImpl) {
+        if let Type::Path(type_path) = &*node.self_ty {
+            if let Some(segment) = type_path.path.segments.last() {
+                self.record_trusted(&node.attrs, &segment.ident);
+            }
+        }
+
+        syn::visit::visit_item_impl(self, node);
+    }
+
+    fn visit_assume_specification(&mut self, node: &'ast AssumeSpecification) {
+        if let Some(segment) = node.path.segments.last() {
+            self.index.trusted.push(TrustedItem {
+                name: segment.ident.to_string(),
+                kind: "assume_specification".to_string(),
+                line: segment.ident.span().start().line,
+                module: self.module_path(),
+            });
+        }
+
+        syn::visit::visit_assume_specification(self, node);
+    }
+
+    fn visit_item_broadcast_group(&mut self, node: &'ast ItemBroadcastGroup) {
+        let members = node
+            .paths
+            .iter()
+            .map(|member| BroadcastMember {
+                path: path_to_string(&member.path),
+                name: path_last_name(&member.path),
+                line: member
+                    .path
+                    .segments
+                    .last()
+                    .map(|seg| seg.ident.span().start().line)
+                    .unwrap_or_else(|| node.ident.span().start().line),
+            })
+            .collect();
+
+        self.index.broadcast_groups.push(BroadcastGroup {
+            name: node.ident.to_string(),
+            line: node.ident.span().start().line,
+            members,
+        });
+
+        syn::visit::visit_item_broadcast_group(self, node);
+    }
+
+    fn visit_item_broadcast_use(&mut self, node: &'ast ItemBroadcastUse) {
+        for used in &node.paths {
+            let line = used
+                .path
+                .segments
+                .last()
+                .map(|seg| seg.ident.span().start().line)
+                .unwrap_or(0);
+            self.index.broadcast_uses.push(BroadcastUse {
+                path: path_to_string(&used.path),
+                name: path_last_name(&used.path),
+                line,
+                enclosing: self.enclosing(),
+            });
+        }
+
+        syn::visit::visit_item_broadcast_use(self, node);
+    }
+
+    fn visit_expr_unary(&mut self, node: &'ast ExprUnary) {
+        if let UnOp::Proof(_) = node.op {
+            // `proof { ... }` block inside exec code
+            let range = self.module_line_range(node.span());
+            self.index.proof_blocks.push(range);
+            self.with_context("proof", |v| syn::visit::visit_expr_unary(v, node));
+            return;
+        }
+
+        match quantifier_kind(&node.op) {
+            Some(kind) => {
+                self.quantifier_stack.push(kind);
+                syn::visit::visit_expr_unary(self, node);
+                self.quantifier_stack.pop();
+            }
+            None => syn::visit::visit_expr_unary(self, node),
+        }
+    }
+
+    fn visit_requires(&mut self, node: &'ast Requires) {
+        self.record_spec_clause(node.span());
+        self.with_context("spec", |v| syn::visit::visit_requires(v, node));
+    }
+
+    fn visit_recommends(&mut self, node: &'ast Recommends) {
+        self.record_spec_clause(node.span());
+        self.with_context("spec", |v| syn::visit::visit_recommends(v, node));
+    }
+
+    fn visit_ensures(&mut self, node: &'ast Ensures) {
+        self.record_spec_clause(node.span());
+        self.with_context("spec", |v| syn::visit::visit_ensures(v, node));
+    }
+
+    fn visit_decreases(&mut self, node: &'ast Decreases) {
+        self.record_spec_clause(node.span());
+        self.with_context("spec", |v| syn::visit::visit_decreases(v, node));
+    }
+
+    fn visit_invariant(&mut self, node: &'ast Invariant) {
+        self.record_spec_clause(node.span());
+        self.with_context("spec", |v| syn::visit::visit_invariant(v, node));
+    }
+
+    fn visit_invariant_except_break(&mut self, node: &'ast InvariantExceptBreak) {
+        self.record_spec_clause(node.span());
+        self.with_context("spec", |v| syn::visit::visit_invariant_except_break(v, node));
+    }
+
+    fn visit_invariant_ensures(&mut self, node: &'ast InvariantEnsures) {
+        self.record_spec_clause(node.span());
+        self.with_context("spec", |v| syn::visit::visit_invariant_ensures(v, node));
+    }
+
+    fn visit_expr_assert(&mut self, node: &'ast ExprAssert) {
+        // The asserted condition and the `requires` of `assert(..) by(..)` are
+        // specs; an `assert(..) by { ... }` body is proof code
+        for attr in &node.attrs {
+            self.visit_attribute(attr);
+        }
+        self.with_context("spec", |v| v.visit_expr(&node.expr));
+        if let Some(requires) = &node.requires {
+            self.visit_requires(requires);
+        }
+        if let Some(body) = &node.body {
+            self.with_context("proof", |v| v.visit_block(body));
+        }
+    }
+
+    // `#![trigger f(x), g(y)]` lists its terms in the attribute itself
+    fn visit_attribute(&mut self, node: &'ast Attribute) {
+        if is_trigger_attr(node) {
+            if let Meta::List(_) = &node.meta {
+                if let Ok(terms) =
+                    node.parse_args_with(Punctuated::<Expr, Token![,]>::parse_terminated)
+                {
+                    for term in &terms {
+                        self.record_trigger(term, trigger_head(term));
+                    }
+                }
+            }
+        }
+
+        syn::visit::visit_attribute(self, node);
+    }
+
+    // Every path expression counts as a reference: call targets, `reveal(f)`
+    // arguments, `broadcast use` paths and broadcast group members
+    fn visit_expr_path(&mut self, node: &'ast ExprPath) {
+        if let Some(last) = node.path.segments.last() {
+            self.record_reference(&last.ident);
+        }
+
+        syn::visit::visit_expr_path(self, node);
+    }
+
+    // `#[trigger] f(x)` marks the attributed expression itself
+    fn visit_expr_call(&mut self, node: &'ast ExprCall) {
+        if let Expr::Path(func) = &*node.func {
+            self.record_call(&func.path);
+        }
+
+        if node.attrs.iter().any(is_trigger_attr) {
+            // Print the term without its own `#[trigger]` attribute
+            let mut term = node.clone();
+            term.attrs.clear();
+            self.record_trigger(&term, call_head(node));
+        }
+
+        syn::visit::visit_expr_call(self, node);
+    }
+
+    fn visit_expr_method_call(&mut self, node: &'ast ExprMethodCall) {
+        self.record_method_call(&node.method);
+        self.record_reference(&node.method);
+
+        if node.attrs.iter().any(is_trigger_attr) {
+            let mut term = node.clone();
+            term.attrs.clear();
+            self.record_trigger(&term, method_call_head(node));
+        }
+
+        syn::visit::visit_expr_method_call(self, node);
+    }
+
+    // `x@` is sugar for `x.view()`
+    fn visit_view(&mut self, node: &'ast View) {
+        let view = Ident::new("view", node.at_token.span());
+        self.record_method_call(&view);
+        self.record_reference(&view);
+
+        syn::visit::visit_view(self, node);
+    }
+
+    fn visit_expr_paren(&mut self, node: &'ast ExprParen) {
+        if node.attrs.iter().any(is_trigger_attr) {
+            self.record_trigger(&node.expr, trigger_head(&node.expr));
+        }
+
+        syn::visit::visit_expr_paren(self, node);
+    }
+}
+
+/// Build the broadcast membership/usage report across all indexed files
+pub fn broadcast_json(indexes: &[(PathBuf, VerusIndex)]) -> serde_json::Value {
+    let mut groups = Vec::new();
+    let mut uses = Vec::new();
+    let mut member_of: BTreeMap<(String, String), Vec<String>> = BTreeMap::new();
+
+    for (file, index) in indexes {
+        let file = file.display().to_string();
+        for group in &index.broadcast_groups {
+            for member in &group.members {
+                member_of
+                    .entry((file.clone(), member.path.clone()))
+                    .or_default()
+                    .push(group.name.clone());
+            }
+            groups.push(json!({
+                "file": file,
+                "name": group.name,
+                "line": group.line,
+                "members": group.members,
+            }));
+        }
+        for used in &index.broadcast_uses {
+            uses.push(json!({
+                "file": file,
+                "path": used.path,
+                "name": used.name,
+                "line": used.line,
+                "enclosing": used.enclosing,
+            }));
+        }
+    }
+
+    let member_of: Vec<_> = member_of
+        .into_iter()
+        .map(|((file, path), groups)| {
+            json!({
+                "file": file,
+                "path": path,
+                "groups": groups,
+            })
+        })
+        .collect();
+
+    json!({
+        "groups": groups,
+        "uses": uses,
+        "member_of": member_of,
+    })
+}
+
+/// Build the trigger index across all indexed files, grouped by head function
+pub fn trigger_json(indexes: &[(PathBuf, VerusIndex)]) -> serde_json::Value {
+    let mut by_function: BTreeMap<String, Vec<serde_json::Value>> = BTreeMap::new();
+
+    for (file, index) in indexes {
+        let file = file.display().to_string();
+        for trigger in &index.triggers {
+            by_function
+                .entry(trigger.function.clone())
+                .or_default()
+                .push(json!({
+                    "file": file,
+                    "line": trigger.line,
+                    "term": trigger.term,
+                    "quantifier": trigger.quantifier,
+                    "enclosing": trigger.enclosing,
+                    "enclosing_line": trigger.enclosing_line,
+                }));
+        }
+    }
+
+    json!({ "triggers": by_function })
+}
+
+/// Mode of every function defined in the indexed files, by name
+fn function_modes(indexes: &[(PathBuf, VerusIndex)]) -> BTreeMap<String, String> {
+    let mut modes = BTreeMap::new();
+    for (_, index) in indexes {
+        for function in &index.functions {
+            modes
+                .entry(function.name.clone())
+                .or_insert_with(|| function.mode.clone());
+        }
+    }
+    modes
+}
+
+/// Build the call graph as JSON: function definitions plus one edge per call site
+pub fn call_graph_json(indexes: &[(PathBuf, VerusIndex)]) -> serde_json::Value {
+    let modes = function_modes(indexes);
+    let mut functions = Vec::new();
+    let mut edges = Vec::new();
+
+    for (file, index) in indexes {
+        let file = file.display().to_string();
+        for function in &index.functions {
+            functions.push(json!({
+                "file": file,
+                "name": function.name,
+                "mode": function.mode,
+                "line": function.line,
+            }));
+        }
+        for call in &index.calls {
+            edges.push(json!({
+                "file": file,
+                "caller": call.caller,
+                "caller_mode": call.caller_mode,
+                "callee": call.callee,
+                "callee_path": call.callee_path,
+                // `null` when the callee is not defined in the indexed files
+                "callee_mode": modes.get(&call.callee),
+                "context": call.context,
+                "line": call.line,
+            }));
+        }
+    }
+
+    json!({
+        "functions": functions,
+        "edges": edges,
+    })
+}
+
+/// Build the call graph in Graphviz DOT format, one edge per (caller, callee, context)
+pub fn call_graph_dot(indexes: &[(PathBuf, VerusIndex)]) -> String {
+    let modes = function_modes(indexes);
+    let mut nodes: BTreeSet<&str> = BTreeSet::new();
+    let mut edges: BTreeSet<(&str, &str, &str)> = BTreeSet::new();
+
+    for (_, index) in indexes {
+        for call in &index.calls {
+            nodes.insert(&call.caller);
+            nodes.insert(&call.callee);
+            edges.insert((&call.caller, &call.callee, &call.context));
+        }
+    }
+
+    let mut dot = String::from("digraph calls {\n    node [shape=box];\n");
+    for node in nodes {
+        // Callees defined outside the indexed files are drawn in gray
+        let (mode, color) = match modes.get(node).map(String::as_str) {
+            Some("spec") => ("spec", "blue"),
+            Some("proof") => ("proof", "darkgreen"),
+            Some(_) => ("exec", "black"),
+            None => ("external", "gray"),
+        };
+        dot.push_str(&format!(
+            "    \"{}\" [label=\"{}\\n({})\", color={}];\n",
+            node, node, mode, color
+        ));
+    }
+    for (caller, callee, context) in edges {
+        let style = match context {
+            "spec" => "dotted",
+            "proof" => "dashed",
+            _ => "solid",
+        };
+        dot.push_str(&format!(
+            "    \"{}\" -> \"{}\" [style={}, label=\"{}\"];\n",
+            caller, callee, style, context
+        ));
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+/// Proof and spec functions whose names are never referenced in the indexed
+/// files, other than from inside their own bodies
+pub fn unused_functions(indexes: &[(PathBuf, VerusIndex)]) -> Vec<(&PathBuf, &FunctionDef)> {
+    let mut referenced: BTreeSet<&str> = BTreeSet::new();
+    for (_, index) in indexes {
+        for reference in &index.references {
+            if reference.enclosing_fn.as_deref() != Some(reference.name.as_str()) {
+                referenced.insert(&reference.name);
+            }
+        }
+    }
+
+    let mut unused = Vec::new();
+    for (file, index) in indexes {
+        for function in &index.functions {
+            let is_ghost = function.mode == "spec" || function.mode == "proof";
+            if is_ghost && !referenced.contains(function.name.as_str()) {
+                unused.push((file, function));
+            }
+        }
+    }
+    unused
+}
@@ -0,0 +1,60 @@
+use std::path::PathBuf;
+
+use crate::tag_visitor::{Tag, TagKind};
+
+/// A `FILE.rs:LINE` position, with an optional `:COLUMN` that is ignored.
+/// Surrounding punctuation is dropped, so positions can be picked out of
+/// verifier output like `--> src/lib.rs:12:5` or `(src/lib.rs:12)`.
+pub fn parse_position(word: &str) -> Option<(PathBuf, usize)> {
+    let word = word.trim_matches(|c: char| !(c.is_alphanumeric() || "/\\._-~".contains(c)));
+    let mut parts = word.split(':');
+    let path = parts.next()?;
+    if !path.ends_with(".rs") {
+        return None;
+    }
+    let line = parts.next()?.parse().ok().filter(|line| *line > 0)?;
+    Some((PathBuf::from(path), line))
+}
+
+/// The items whose range holds `line`, outermost first
+pub fn enclosing_items(tags: &[Tag], line: usize) -> Vec<&Tag> {
+    let mut chain: Vec<&Tag> = tags
+        .iter()
+        .filter(|tag| is_item(tag) && tag.start_line <= line && line <= tag.end_line)
+        .collect();
+    chain.sort_by(|a, b| a.start_byte.cmp(&b.start_byte).then(b.end_byte.cmp(&a.end_byte)));
+    chain
+}
+
+/// Broadcast members, `broadcast use` and triggers are tagged where they
+/// appear, but are not items that contain code
+fn is_item(tag: &Tag) -> bool {
+    !matches!(
+        tag.kind,
+        TagKind::BroadcastMember | TagKind::BroadcastUse | TagKind::Trigger
+    )
+}
+
+/// An item as it is introduced in source, e.g. `proof fn lemma_len`
+pub fn label(tag: &Tag) -> String {
+    let name = tag.identifier().unwrap_or(&tag.name);
+    match tag.kind {
+        TagKind::Function => match tag.mode {
+            Some(mode @ ("spec" | "proof")) => format!("{} fn {}", mode, name),
+            _ => format!("fn {}", name),
+        },
+        TagKind::Struct => format!("struct {}", name),
+        TagKind::Enum => format!("enum {}", name),
+        TagKind::Trait => format!("trait {}", name),
+        TagKind::Const => format!("const {}", name),
+        TagKind::Static => format!("static {}", name),
+        TagKind::Type => format!("type {}", name),
+        TagKind::Module => format!("mod {}", name),
+        TagKind::Macro => format!("macro_rules! {}", name),
+        TagKind::BroadcastGroup => format!("broadcast group {}", name),
+        TagKind::Impl => tag.header.clone().unwrap_or_else(|| name.to_string()),
+        // Variants are named by themselves; specifications already read
+        // as they are written
+        _ => name.to_string(),
+    }
+}
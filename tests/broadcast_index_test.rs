@@ -0,0 +1,90 @@
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+const BROADCAST_SOURCE: &str = r#"
+verus! {
+
+pub broadcast proof fn lemma_a(x: int)
+    ensures #[trigger] (x + 0) == x,
+{
+}
+
+pub broadcast proof fn lemma_b(x: int)
+    ensures #[trigger] (x * 1) == x,
+{
+}
+
+pub broadcast group group_arith {
+    lemma_a,
+    lemma_b,
+}
+
+proof fn uses_group() {
+    broadcast use group_arith;
+}
+
+}
+"#;
+
+#[test]
+fn test_broadcast_member_tags() {
+    let temp_dir = TempDir::new().unwrap();
+    let test_file = temp_dir.path().join("broadcast.rs");
+    fs::write(&test_file, BROADCAST_SOURCE).unwrap();
+    let tags_file = temp_dir.path().join("TAGS");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_verus-etags"))
+        .arg("-o")
+        .arg(&tags_file)
+        .arg(&test_file)
+        .output()
+        .expect("Failed to execute verus-etags");
+
+    assert!(output.status.success(), "verus-etags failed: {:?}", output);
+
+    let tags_content = fs::read_to_string(&tags_file).unwrap();
+    assert!(tags_content.contains("\x7fgroup_arith\x01"), "Missing group tag");
+    assert!(tags_content.contains("\x7fgroup_arith::lemma_a\x01"), "Missing member tag for lemma_a");
+    assert!(tags_content.contains("\x7fgroup_arith::lemma_b\x01"), "Missing member tag for lemma_b");
+    assert!(tags_content.contains("\x7fbroadcast use group_arith\x01"), "Missing broadcast use tag");
+}
+
+#[test]
+fn test_broadcast_json_report() {
+    let temp_dir = TempDir::new().unwrap();
+    let test_file = temp_dir.path().join("broadcast.rs");
+    fs::write(&test_file, BROADCAST_SOURCE).unwrap();
+    let tags_file = temp_dir.path().join("TAGS");
+    let json_file = temp_dir.path().join("broadcast.json");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_verus-etags"))
+        .arg("-o")
+        .arg(&tags_file)
+        .arg("--broadcast-json")
+        .arg(&json_file)
+        .arg(&test_file)
+        .output()
+        .expect("Failed to execute verus-etags");
+
+    assert!(output.status.success(), "verus-etags failed: {:?}", output);
+
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&json_file).unwrap()).unwrap();
+
+    let groups = report["groups"].as_array().unwrap();
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0]["name"], "group_arith");
+    assert_eq!(groups[0]["members"].as_array().unwrap().len(), 2);
+
+    let member_of = report["member_of"].as_array().unwrap();
+    assert_eq!(member_of.len(), 2);
+    let lemma_a = member_of.iter().find(|m| m["path"] == "lemma_a").unwrap();
+    assert_eq!(lemma_a["file"], test_file.display().to_string());
+    assert_eq!(lemma_a["groups"][0], "group_arith");
+
+    let uses = report["uses"].as_array().unwrap();
+    assert_eq!(uses.len(), 1);
+    assert_eq!(uses[0]["path"], "group_arith");
+    assert_eq!(uses[0]["enclosing"], "uses_group");
+}
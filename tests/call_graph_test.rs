@@ -0,0 +1,97 @@
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+const CALL_SOURCE: &str = r#"
+verus! {
+
+spec fn double(x: int) -> int {
+    x + x
+}
+
+spec fn quadruple(x: int) -> int {
+    double(double(x))
+}
+
+proof fn lemma_double(x: int)
+    ensures double(x) == 2 * x,
+{
+}
+
+fn exec_double(x: u32) -> (r: u32)
+    requires x < 1000,
+    ensures r == double(x as int),
+{
+    proof {
+        lemma_double(x as int);
+    }
+    helper(x)
+}
+
+fn helper(x: u32) -> u32 {
+    x * 2
+}
+
+}
+"#;
+
+fn run_call_graph(temp_dir: &TempDir, flag: &str, output_name: &str) -> String {
+    let test_file = temp_dir.path().join("calls.rs");
+    fs::write(&test_file, CALL_SOURCE).unwrap();
+    let tags_file = temp_dir.path().join("TAGS");
+    let graph_file = temp_dir.path().join(output_name);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_verus-etags"))
+        .arg("-o")
+        .arg(&tags_file)
+        .arg(flag)
+        .arg(&graph_file)
+        .arg(&test_file)
+        .output()
+        .expect("Failed to execute verus-etags");
+
+    assert!(output.status.success(), "verus-etags failed: {:?}", output);
+    fs::read_to_string(&graph_file).unwrap()
+}
+
+#[test]
+fn test_call_graph_json_contexts() {
+    let temp_dir = TempDir::new().unwrap();
+    let content = run_call_graph(&temp_dir, "--call-graph-json", "calls.json");
+    let graph: serde_json::Value = serde_json::from_str(&content).unwrap();
+
+    let edges = graph["edges"].as_array().unwrap();
+    let has_edge = |caller: &str, callee: &str, context: &str| {
+        edges.iter().any(|edge| {
+            edge["caller"] == caller && edge["callee"] == callee && edge["context"] == context
+        })
+    };
+
+    assert!(has_edge("quadruple", "double", "spec"), "Missing spec body call");
+    assert!(has_edge("lemma_double", "double", "spec"), "Missing ensures call");
+    assert!(has_edge("exec_double", "double", "spec"), "Missing ensures call in exec fn");
+    assert!(has_edge("exec_double", "lemma_double", "proof"), "Missing proof block call");
+    assert!(has_edge("exec_double", "helper", "exec"), "Missing exec call");
+
+    let functions = graph["functions"].as_array().unwrap();
+    let mode_of = |name: &str| {
+        functions
+            .iter()
+            .find(|function| function["name"] == name)
+            .map(|function| function["mode"].clone())
+    };
+    assert_eq!(mode_of("double").unwrap(), "spec");
+    assert_eq!(mode_of("lemma_double").unwrap(), "proof");
+    assert_eq!(mode_of("exec_double").unwrap(), "exec");
+}
+
+#[test]
+fn test_call_graph_dot() {
+    let temp_dir = TempDir::new().unwrap();
+    let dot = run_call_graph(&temp_dir, "--call-graph-dot", "calls.dot");
+
+    assert!(dot.starts_with("digraph calls {"));
+    assert!(dot.contains("\"exec_double\" -> \"lemma_double\" [style=dashed, label=\"proof\"];"));
+    assert!(dot.contains("\"exec_double\" -> \"helper\" [style=solid, label=\"exec\"];"));
+    assert!(dot.contains("\"quadruple\" -> \"double\" [style=dotted, label=\"spec\"];"));
+}
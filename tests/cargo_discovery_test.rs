@@ -0,0 +1,95 @@
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+mod common;
+use common::write;
+
+// A workspace with one member crate that depends on a crate outside the workspace
+fn make_workspace() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+
+    write(root, "Cargo.toml", "[workspace]\nmembers = [\"crates/*\"]\n");
+    write(
+        root,
+        "crates/app/Cargo.toml",
+        "[package]\nname = \"app\"\n\n[dependencies]\nhelper = { path = \"../../helper\" }\n",
+    );
+    write(
+        root,
+        "crates/app/src/lib.rs",
+        "mod a;\n#[path = \"other/b_impl.rs\"]\nmod b;\nmod inline {\n    mod nested;\n}\npub fn lib_root() {}\n",
+    );
+    write(root, "crates/app/src/a.rs", "mod c;\npub fn in_a() {}\n");
+    write(root, "crates/app/src/a/c.rs", "pub fn in_c() {}\n");
+    write(root, "crates/app/src/other/b_impl.rs", "pub fn in_b() {}\n");
+    write(root, "crates/app/src/inline/nested.rs", "pub fn in_nested() {}\n");
+    write(root, "crates/app/src/stale.rs", "pub fn stale_file() {}\n");
+    write(root, "crates/app/src/main.rs", "fn main_root() {}\n");
+    write(root, "crates/app/src/bin/tool.rs", "fn bin_root() {}\n");
+    write(root, "crates/app/tests/it.rs", "fn test_root() {}\n");
+    write(root, "crates/app/examples/demo/main.rs", "fn example_root() {}\n");
+
+    write(root, "helper/Cargo.toml", "[package]\nname = \"helper\"\n");
+    write(root, "helper/src/lib.rs", "pub fn helper_root() {}\n");
+
+    temp_dir
+}
+
+fn run_verus_etags(root: &Path, manifest: &str) -> String {
+    let manifest = root.join(manifest);
+    common::run_verus_etags(root, &["-o", "TAGS", "--manifest-path", manifest.to_str().unwrap()]);
+    fs::read_to_string(root.join("TAGS")).unwrap()
+}
+
+#[test]
+fn test_manifest_follows_module_tree() {
+    let temp_dir = make_workspace();
+    let tags_content = run_verus_etags(temp_dir.path(), "Cargo.toml");
+
+    assert!(tags_content.contains("lib_root"), "Should include the lib root");
+    assert!(tags_content.contains("in_a"), "Should follow mod a");
+    assert!(tags_content.contains("in_c"), "Should follow nested mod c");
+    assert!(tags_content.contains("in_b"), "Should follow #[path]");
+    assert!(tags_content.contains("in_nested"), "Should follow mod inside inline mod");
+    assert!(!tags_content.contains("stale_file"), "Should skip files outside the module tree");
+}
+
+#[test]
+fn test_manifest_targets_and_path_dependencies() {
+    let temp_dir = make_workspace();
+    let tags_content = run_verus_etags(temp_dir.path(), "Cargo.toml");
+
+    assert!(tags_content.contains("main_root"), "Should include src/main.rs");
+    assert!(tags_content.contains("bin_root"), "Should include src/bin/*.rs");
+    assert!(tags_content.contains("test_root"), "Should include tests/*.rs");
+    assert!(tags_content.contains("example_root"), "Should include examples/*/main.rs");
+    assert!(tags_content.contains("helper_root"), "Should include path dependencies");
+}
+
+#[test]
+fn test_manifest_of_single_member() {
+    let temp_dir = make_workspace();
+    let tags_content = run_verus_etags(temp_dir.path(), "crates/app/Cargo.toml");
+
+    assert!(tags_content.contains("lib_root"));
+    assert!(tags_content.contains("helper_root"));
+}
+
+#[test]
+fn test_broken_crate_root_keeps_module_tree() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    write(root, "Cargo.toml", "[package]\nname = \"broken\"\n");
+    write(root, "src/lib.rs", "mod a;\n\nfn half_written(x: u32 {\n}\n\npub fn lib_root() {}\n");
+    write(root, "src/a.rs", "pub fn in_a() {}\n");
+
+    let output = common::run_verus_etags(root, &["-o", "TAGS", "--manifest-path", "Cargo.toml"]);
+
+    let tags_content = fs::read_to_string(root.join("TAGS")).unwrap();
+    assert!(tags_content.contains("in_a"), "Module of a broken root is tagged: {}", tags_content);
+    assert!(tags_content.contains("lib_root"), "TAGS: {}", tags_content);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("lib.rs:"), "The broken root is reported: {}", stderr);
+}
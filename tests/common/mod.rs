@@ -0,0 +1,29 @@
+// Helpers shared by the integration tests; each test crate uses only some
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+/// Write `content` to `relative` under `root`, creating its directories
+pub fn write(root: &Path, relative: &str, content: &str) {
+    let path = root.join(relative);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+/// Run verus-etags in `dir` with `args`, whether or not it succeeds
+pub fn verus_etags(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_verus-etags"))
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .expect("Failed to execute verus-etags")
+}
+
+/// Run verus-etags in `dir` with `args` and check that it succeeded
+pub fn run_verus_etags(dir: &Path, args: &[&str]) -> Output {
+    let output = verus_etags(dir, args);
+    assert!(output.status.success(), "verus-etags failed: {:?}", output);
+    output
+}
@@ -0,0 +1,80 @@
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+mod common;
+use common::write;
+
+const LOCK_FILE: &str = r#"
+version = 3
+
+[[package]]
+name = "app"
+version = "0.1.0"
+dependencies = ["from_registry", "from_vendor", "not_fetched"]
+
+[[package]]
+name = "from_registry"
+version = "1.2.3"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "from_vendor"
+version = "0.4.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "not_fetched"
+version = "9.9.9"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+"#;
+
+#[test]
+fn test_deps_tags_from_registry_and_vendor() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path().join("project");
+    let cargo_home = temp_dir.path().join("cargo_home");
+
+    write(&root, "Cargo.toml", "[package]\nname = \"app\"\nversion = \"0.1.0\"\n");
+    write(&root, "Cargo.lock", LOCK_FILE);
+    write(&root, "src/lib.rs", "pub fn app_fn() {}\n");
+    write(
+        &root,
+        "vendor/from_vendor/Cargo.toml",
+        "[package]\nname = \"from_vendor\"\nversion = \"0.4.0\"\n",
+    );
+    write(&root, "vendor/from_vendor/src/lib.rs", "pub fn vendored_fn() {}\n");
+
+    let registry = cargo_home.join("registry/src/index.crates.io-6f17d22bba15001f/from_registry-1.2.3");
+    write(
+        &registry,
+        "Cargo.toml",
+        "[package]\nname = \"from_registry\"\nversion = \"1.2.3\"\n",
+    );
+    write(&registry, "src/lib.rs", "mod inner;\npub fn registry_fn() {}\n");
+    write(&registry, "src/inner.rs", "pub fn registry_inner_fn() {}\n");
+    write(&registry, "tests/it.rs", "fn registry_test_fn() {}\n");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_verus-etags"))
+        .current_dir(&root)
+        .env("CARGO_HOME", &cargo_home)
+        .args(["-o", "TAGS", "--manifest-path", "Cargo.toml", "--deps-tags", "TAGS.deps"])
+        .output()
+        .expect("Failed to execute verus-etags");
+
+    assert!(output.status.success(), "verus-etags failed: {:?}", output);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("1 locked dependencies have no local source"), "stderr: {}", stderr);
+
+    let main_tags = fs::read_to_string(root.join("TAGS")).unwrap();
+    assert!(main_tags.contains("app_fn"));
+    assert!(main_tags.contains("\x0c\nTAGS.deps,include\n"), "Missing include line: {:?}", main_tags);
+    assert!(!main_tags.contains("registry_fn"), "Dependency tags belong in TAGS.deps");
+
+    let deps_tags = fs::read_to_string(root.join("TAGS.deps")).unwrap();
+    assert!(deps_tags.contains("\x7fregistry_fn\x01"));
+    assert!(deps_tags.contains("\x7fregistry_inner_fn\x01"));
+    assert!(deps_tags.contains("\x7fvendored_fn\x01"));
+    assert!(!deps_tags.contains("registry_test_fn"), "Only library targets of dependencies are tagged");
+    assert!(!deps_tags.contains("app_fn"));
+}
@@ -0,0 +1,87 @@
+use std::path::Path;
+use tempfile::TempDir;
+
+mod common;
+
+const FILES: [(&str, &str); 5] = [
+    ("src/a.rs", "fn in_a() {}\n"),
+    ("src/b.rs", "fn in_b() {}\n"),
+    ("src/nested/c.rs", "fn in_c() {}\n"),
+    ("src/nested/deeper/d.rs", "fn in_d() {}\n"),
+    ("z.rs", "fn in_z() {}\n"),
+];
+
+fn create_tree(root: &Path, order: &[usize]) {
+    for &index in order {
+        let (relative, content) = FILES[index];
+        common::write(root, relative, content);
+    }
+}
+
+fn tags_bytes(root: &Path, paths: &[&str]) -> Vec<u8> {
+    common::run_verus_etags(root, &[&["-o", "-"], paths].concat()).stdout
+}
+
+fn section_names(tags: &[u8]) -> Vec<String> {
+    String::from_utf8_lossy(tags)
+        .split('\x0c')
+        .filter_map(|section| section.strip_prefix('\n'))
+        .filter_map(|section| section.lines().next())
+        .filter_map(|header| header.rsplit_once(',').map(|(name, _)| name.to_string()))
+        .collect()
+}
+
+#[test]
+fn test_output_independent_of_creation_order() {
+    let forward = TempDir::new().unwrap();
+    let backward = TempDir::new().unwrap();
+    let shuffled = TempDir::new().unwrap();
+    create_tree(forward.path(), &[0, 1, 2, 3, 4]);
+    create_tree(backward.path(), &[4, 3, 2, 1, 0]);
+    create_tree(shuffled.path(), &[2, 4, 0, 3, 1]);
+
+    let expected = tags_bytes(forward.path(), &["."]);
+    assert_eq!(tags_bytes(backward.path(), &["."]), expected);
+    assert_eq!(tags_bytes(shuffled.path(), &["."]), expected);
+    assert_eq!(
+        section_names(&expected),
+        vec!["src/a.rs", "src/b.rs", "src/nested/c.rs", "src/nested/deeper/d.rs", "z.rs"]
+    );
+}
+
+#[test]
+fn test_argument_order_does_not_matter() {
+    let temp_dir = TempDir::new().unwrap();
+    create_tree(temp_dir.path(), &[0, 1, 2, 3, 4]);
+
+    assert_eq!(
+        tags_bytes(temp_dir.path(), &["z.rs", "src"]),
+        tags_bytes(temp_dir.path(), &["src", "z.rs"])
+    );
+}
+
+#[test]
+fn test_overlapping_arguments_are_deduplicated() {
+    let temp_dir = TempDir::new().unwrap();
+    create_tree(temp_dir.path(), &[0, 1, 2, 3, 4]);
+
+    let tags = tags_bytes(temp_dir.path(), &["src", "src/nested", "src/a.rs", "./src/a.rs"]);
+    assert_eq!(
+        section_names(&tags),
+        vec!["src/a.rs", "src/b.rs", "src/nested/c.rs", "src/nested/deeper/d.rs"]
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_symlinked_files_are_deduplicated() {
+    let temp_dir = TempDir::new().unwrap();
+    create_tree(temp_dir.path(), &[0, 1, 2, 3, 4]);
+    std::os::unix::fs::symlink("nested", temp_dir.path().join("src/link")).unwrap();
+
+    let tags = tags_bytes(temp_dir.path(), &["src"]);
+    assert_eq!(
+        section_names(&tags),
+        vec!["src/a.rs", "src/b.rs", "src/link/c.rs", "src/link/deeper/d.rs"]
+    );
+}
@@ -0,0 +1,66 @@
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use tempfile::TempDir;
+
+fn make_sources(root: &Path) {
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::write(root.join("src/a.rs"), "fn listed_a() {}\n").unwrap();
+    fs::write(root.join("src/b.rs"), "fn listed_b() {}\n").unwrap();
+    fs::write(root.join("src/c.rs"), "fn not_listed() {}\n").unwrap();
+}
+
+fn run_with_stdin(root: &Path, args: &[&str], stdin: &[u8]) -> String {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_verus-etags"))
+        .current_dir(root)
+        .args(["-o", "TAGS"])
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to execute verus-etags");
+    child.stdin.take().unwrap().write_all(stdin).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success(), "verus-etags failed: {:?}", output);
+    fs::read_to_string(root.join("TAGS")).unwrap()
+}
+
+#[test]
+fn test_file_list_from_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    make_sources(root);
+    fs::write(root.join("files.txt"), "src/a.rs\r\n\nsrc/b.rs\n").unwrap();
+
+    let tags_content = run_with_stdin(root, &["-L", "files.txt"], b"");
+    assert!(tags_content.contains("listed_a"));
+    assert!(tags_content.contains("listed_b"));
+    assert!(!tags_content.contains("not_listed"));
+}
+
+#[test]
+fn test_file_list_from_stdin() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    make_sources(root);
+
+    let tags_content = run_with_stdin(root, &["-L", "-"], b"src/a.rs\nsrc/b.rs\n");
+    assert!(tags_content.contains("listed_a"));
+    assert!(tags_content.contains("listed_b"));
+    assert!(!tags_content.contains("not_listed"));
+}
+
+#[test]
+fn test_file_list_null_separated() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    make_sources(root);
+    fs::write(root.join("src/with space.rs"), "fn spaced_name() {}\n").unwrap();
+
+    let tags_content = run_with_stdin(root, &["-L", "-", "--null"], b"src/a.rs\0src/with space.rs\0");
+    assert!(tags_content.contains("listed_a"));
+    assert!(tags_content.contains("spaced_name"));
+    assert!(!tags_content.contains("listed_b"));
+}
@@ -0,0 +1,101 @@
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+mod common;
+
+// A small Cargo project with build output, an ignored directory and real sources
+fn make_project() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::create_dir_all(root.join("target/debug/build")).unwrap();
+    fs::create_dir_all(root.join("generated")).unwrap();
+    fs::create_dir_all(root.join("vendor")).unwrap();
+
+    fs::write(root.join("Cargo.toml"), "[package]\nname = \"demo\"\n").unwrap();
+    fs::write(root.join("src/lib.rs"), "pub fn real_source() {}\n").unwrap();
+    fs::write(root.join("src/extra.rs"), "pub fn extra_source() {}\n").unwrap();
+    fs::write(root.join("target/debug/build/out.rs"), "pub fn build_output() {}\n").unwrap();
+    fs::write(root.join("generated/gen.rs"), "pub fn git_ignored() {}\n").unwrap();
+    fs::write(root.join("vendor/dep.rs"), "pub fn dot_ignored() {}\n").unwrap();
+    fs::write(root.join(".gitignore"), "generated/\n").unwrap();
+    fs::write(root.join(".ignore"), "vendor/\n").unwrap();
+
+    temp_dir
+}
+
+fn run_verus_etags(root: &Path, extra_args: &[&str]) -> String {
+    common::run_verus_etags(root, &[&["-o", "TAGS"], extra_args, &["."]].concat());
+    fs::read_to_string(root.join("TAGS")).unwrap()
+}
+
+#[test]
+fn test_ignore_files_and_target_skipped_by_default() {
+    let temp_dir = make_project();
+    let tags_content = run_verus_etags(temp_dir.path(), &[]);
+
+    assert!(tags_content.contains("real_source"), "Should include src/lib.rs");
+    assert!(!tags_content.contains("build_output"), "Should skip target/");
+    assert!(!tags_content.contains("git_ignored"), "Should honor .gitignore");
+    assert!(!tags_content.contains("dot_ignored"), "Should honor .ignore");
+}
+
+#[test]
+fn test_no_ignore_flag() {
+    let temp_dir = make_project();
+    let tags_content = run_verus_etags(temp_dir.path(), &["--no-ignore"]);
+
+    assert!(tags_content.contains("real_source"));
+    assert!(tags_content.contains("build_output"), "--no-ignore should include target/");
+    assert!(tags_content.contains("git_ignored"), "--no-ignore should ignore .gitignore");
+    assert!(tags_content.contains("dot_ignored"), "--no-ignore should ignore .ignore");
+}
+
+#[test]
+fn test_exclude_glob() {
+    let temp_dir = make_project();
+    let tags_content = run_verus_etags(temp_dir.path(), &["--exclude", "extra.rs"]);
+
+    assert!(tags_content.contains("real_source"));
+    assert!(!tags_content.contains("extra_source"), "--exclude should skip extra.rs");
+}
+
+#[test]
+fn test_include_glob() {
+    let temp_dir = make_project();
+    let tags_content = run_verus_etags(temp_dir.path(), &["--no-ignore", "--include", "src/lib.rs"]);
+
+    assert!(tags_content.contains("real_source"));
+    assert!(!tags_content.contains("extra_source"), "--include should only keep src/lib.rs");
+    assert!(!tags_content.contains("build_output"), "--include should only keep src/lib.rs");
+}
+
+#[test]
+fn test_target_module_without_cargo_manifest_is_kept() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    fs::create_dir_all(root.join("src/target")).unwrap();
+    fs::write(root.join("src/target/mod.rs"), "pub fn target_module() {}\n").unwrap();
+
+    let tags_content = run_verus_etags(root, &[]);
+    assert!(tags_content.contains("target_module"), "A src/target module is not Cargo output");
+}
+
+#[test]
+fn test_custom_target_dir_with_cachedir_tag_skipped() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    fs::create_dir_all(root.join("build/debug")).unwrap();
+    fs::write(
+        root.join("build/CACHEDIR.TAG"),
+        "Signature: 8a477f597d28d172789f06886806bc55\n# This file is a cache directory tag created by cargo.\n",
+    )
+    .unwrap();
+    fs::write(root.join("build/debug/out.rs"), "pub fn build_output() {}\n").unwrap();
+    fs::write(root.join("lib.rs"), "pub fn real_source() {}\n").unwrap();
+
+    let tags_content = run_verus_etags(root, &[]);
+    assert!(tags_content.contains("real_source"));
+    assert!(!tags_content.contains("build_output"), "A CARGO_TARGET_DIR=build is Cargo output");
+}
@@ -0,0 +1,49 @@
+use std::fs;
+use tempfile::TempDir;
+
+mod common;
+use common::{run_verus_etags, write};
+
+#[test]
+fn test_include_tags_sections() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    write(root, "main.rs", "fn main() {}\n");
+
+    run_verus_etags(
+        root,
+        &["-o", "TAGS", "--include-tags", "vstd/TAGS", "--include-tags", "/opt/other/TAGS", "main.rs"],
+    );
+
+    let tags_content = fs::read_to_string(root.join("TAGS")).unwrap();
+    assert!(tags_content.contains("\x0c\nvstd/TAGS,include\n"));
+    assert!(tags_content.contains("\x0c\n/opt/other/TAGS,include\n"));
+    assert!(tags_content.contains("\x7fmain\x01"));
+}
+
+#[test]
+fn test_split_per_crate() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    write(root, "crates/app/Cargo.toml", "[package]\nname = \"app\"\n");
+    write(root, "crates/app/src/lib.rs", "pub fn app_fn() {}\n");
+    write(root, "vstd/seq.rs", "pub fn vstd_fn() {}\n");
+    write(root, "top.rs", "pub fn top_fn() {}\n");
+
+    run_verus_etags(root, &["-o", "TAGS", "--split-per-crate", "crates", "vstd", "top.rs"]);
+
+    let app_tags = fs::read_to_string(root.join("crates/app/TAGS")).unwrap();
+    assert!(app_tags.contains("\x0c\nsrc/lib.rs,"), "Names are relative to the crate: {:?}", app_tags);
+    assert!(app_tags.contains("\x7fapp_fn\x01"));
+
+    let vstd_tags = fs::read_to_string(root.join("vstd/TAGS")).unwrap();
+    assert!(vstd_tags.contains("\x0c\nseq.rs,"));
+    assert!(vstd_tags.contains("\x7fvstd_fn\x01"));
+
+    let root_tags = fs::read_to_string(root.join("TAGS")).unwrap();
+    assert!(root_tags.contains("\x0c\ncrates/app/TAGS,include\n"));
+    assert!(root_tags.contains("\x0c\nvstd/TAGS,include\n"));
+    assert!(root_tags.contains("\x7ftop_fn\x01"));
+    assert!(!root_tags.contains("app_fn"));
+    assert!(!root_tags.contains("vstd_fn"));
+}
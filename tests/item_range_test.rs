@@ -0,0 +1,63 @@
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+const SOURCE: &str = "\
+use std::fmt;
+
+/// The area of a circle
+/// of radius `r`
+#[inline]
+pub fn área(r: u32) -> u32 {
+    r * r * 3
+}
+
+#[derive(Debug)]
+enum Shape {
+    /// A round one
+    Circle,
+}
+";
+
+fn json_tags(source: &str) -> Vec<serde_json::Value> {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("lib.rs"), source).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_verus-etags"))
+        .current_dir(temp_dir.path())
+        .args(["--format", "json", "-o", "-", "lib.rs"])
+        .output()
+        .expect("Failed to execute verus-etags");
+    assert!(output.status.success(), "verus-etags failed: {:?}", output);
+
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+fn item_text<'a>(tags: &[serde_json::Value], name: &str) -> &'a str {
+    let tag = tags.iter().find(|tag| tag["name"] == name).unwrap();
+    let (start, end) = (tag["start_byte"].as_u64().unwrap() as usize, tag["end_byte"].as_u64().unwrap() as usize);
+    &SOURCE[start..end]
+}
+
+#[test]
+fn test_item_range_includes_doc_comments_and_attributes() {
+    let tags = json_tags(SOURCE);
+    let function = tags.iter().find(|tag| tag["name"] == "área").unwrap();
+    assert_eq!(function["line"], 6);
+    assert_eq!(function["start_line"], 3);
+    assert_eq!(function["end_line"], 8);
+
+    let text = item_text(&tags, "área");
+    assert!(text.starts_with("/// The area of a circle\n"), "{:?}", text);
+    assert!(text.ends_with("r * r * 3\n}"), "{:?}", text);
+}
+
+#[test]
+fn test_nested_item_ranges() {
+    let tags = json_tags(SOURCE);
+    assert_eq!(item_text(&tags, "Shape"), &SOURCE[SOURCE.find("#[derive").unwrap()..SOURCE.len() - 1]);
+    assert_eq!(item_text(&tags, "Shape::Circle"), "/// A round one\n    Circle");
+}
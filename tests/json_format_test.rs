@@ -0,0 +1,106 @@
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+const SOURCE: &str = "\
+mod shapes {
+    pub struct Circle {
+        radius: u32,
+    }
+
+    impl Circle {
+        pub fn area(&self) -> u32 {
+            self.radius * self.radius * 3
+        }
+    }
+}
+";
+
+fn json_tags(temp_dir: &TempDir) -> Vec<serde_json::Value> {
+    fs::write(temp_dir.path().join("shapes.rs"), SOURCE).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_verus-etags"))
+        .current_dir(temp_dir.path())
+        .args(["--format", "json", "-o", "-", "shapes.rs"])
+        .output()
+        .expect("Failed to execute verus-etags");
+    assert!(output.status.success(), "verus-etags failed: {:?}", output);
+
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("Each line should be a JSON object"))
+        .collect()
+}
+
+fn find<'a>(tags: &'a [serde_json::Value], name: &str) -> &'a serde_json::Value {
+    tags.iter()
+        .find(|tag| tag["name"] == name)
+        .unwrap_or_else(|| panic!("No tag named {}: {:?}", name, tags))
+}
+
+#[test]
+fn test_one_object_per_tag_with_metadata() {
+    let temp_dir = TempDir::new().unwrap();
+    let tags = json_tags(&temp_dir);
+
+    let area = find(&tags, "area");
+    assert_eq!(area["file"], "shapes.rs");
+    assert_eq!(area["kind"], "function");
+    assert_eq!(area["line"], 7);
+    assert_eq!(area["column"], 16);
+    assert_eq!(area["end_line"], 9);
+    assert_eq!(area["pattern"], "        pub fn area(&self) -> u32 {");
+    assert_eq!(area["scope"], "shapes::Circle");
+    assert_eq!(area["mode"], "exec");
+    let offset = SOURCE.find("        pub fn area").unwrap();
+    assert_eq!(area["byte_offset"], offset);
+
+    let circle = find(&tags, "Circle");
+    assert_eq!(circle["kind"], "struct");
+    assert_eq!(circle["end_line"], 4);
+    assert_eq!(circle["scope"], "shapes");
+    assert_eq!(circle["mode"], serde_json::Value::Null);
+
+    let module = find(&tags, "shapes");
+    assert_eq!(module["kind"], "module");
+    assert_eq!(module["scope"], serde_json::Value::Null);
+    assert_eq!(find(&tags, "impl Circle")["kind"], "impl");
+}
+
+#[test]
+fn test_json_matches_etags_entries() {
+    let temp_dir = TempDir::new().unwrap();
+    let tags = json_tags(&temp_dir);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_verus-etags"))
+        .current_dir(temp_dir.path())
+        .args(["-o", "-", "shapes.rs"])
+        .output()
+        .expect("Failed to execute verus-etags");
+    let etags = String::from_utf8(output.stdout).unwrap();
+
+    for tag in &tags {
+        let entry = format!(
+            "{}\x7f{}\x01{},{}\n",
+            tag["pattern"].as_str().unwrap(),
+            tag["name"].as_str().unwrap(),
+            tag["line"],
+            tag["byte_offset"]
+        );
+        assert!(etags.contains(&entry), "Missing etags entry {:?} in {:?}", entry, etags);
+    }
+    assert_eq!(etags.matches('\x7f').count(), tags.len());
+}
+
+#[test]
+fn test_json_rejects_include_sections() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("shapes.rs"), SOURCE).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_verus-etags"))
+        .current_dir(temp_dir.path())
+        .args(["--format", "json", "--include-tags", "OTHER", "shapes.rs"])
+        .output()
+        .expect("Failed to execute verus-etags");
+    assert!(!output.status.success());
+}
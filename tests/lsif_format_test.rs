@@ -0,0 +1,111 @@
+use serde_json::Value;
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+fn lsif_records(temp_dir: &TempDir) -> Vec<Value> {
+    fs::write(temp_dir.path().join("lib.rs"), "mod shapes {\n    pub fn helper() {}\n}\n").unwrap();
+    fs::write(temp_dir.path().join("main.rs"), "fn main() {\n    helper();\n}\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_verus-etags"))
+        .current_dir(temp_dir.path())
+        .args(["--format", "lsif", "-o", "-", "lib.rs", "main.rs"])
+        .output()
+        .expect("Failed to execute verus-etags");
+    assert!(output.status.success(), "verus-etags failed: {:?}", output);
+
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("Each line should be JSON"))
+        .collect()
+}
+
+fn edges_from<'a>(records: &'a [Value], label: &str, out_v: &Value) -> Vec<&'a Value> {
+    records
+        .iter()
+        .filter(|record| record["type"] == "edge" && record["label"] == label && record["outV"] == *out_v)
+        .collect()
+}
+
+fn range_at(records: &[Value], line: u64, character: u64) -> &Value {
+    records
+        .iter()
+        .find(|record| {
+            record["label"] == "range"
+                && record["start"]["line"] == line
+                && record["start"]["character"] == character
+        })
+        .unwrap_or_else(|| panic!("No range at {}:{}", line, character))
+}
+
+#[test]
+fn test_documents_and_definition_ranges() {
+    let temp_dir = TempDir::new().unwrap();
+    let records = lsif_records(&temp_dir);
+
+    assert_eq!(records[0]["label"], "metaData");
+    let uris: Vec<&str> = records
+        .iter()
+        .filter(|record| record["label"] == "document")
+        .map(|record| record["uri"].as_str().unwrap())
+        .collect();
+    assert_eq!(uris.len(), 2);
+    assert!(uris.iter().all(|uri| uri.starts_with("file:///")), "{:?}", uris);
+    assert!(uris[0].ends_with("/lib.rs"));
+
+    let helper = range_at(&records, 1, 11);
+    assert_eq!(helper["end"]["character"], 17);
+    assert_eq!(helper["tag"]["type"], "definition");
+    assert_eq!(helper["tag"]["text"], "helper");
+    assert_eq!(helper["tag"]["kind"], 12);
+
+    // helper is nested in the shapes module
+    let symbols = records.iter().find(|record| record["label"] == "documentSymbolResult").unwrap();
+    let module = &symbols["result"][0];
+    assert_eq!(module["children"][0]["id"], helper["id"]);
+}
+
+#[test]
+fn test_reference_resolves_to_definition() {
+    let temp_dir = TempDir::new().unwrap();
+    let records = lsif_records(&temp_dir);
+
+    let call = range_at(&records, 1, 4);
+    assert_eq!(call["tag"]["type"], "reference");
+    let result_set = &edges_from(&records, "next", &call["id"])[0]["inV"];
+    let definition_result = &edges_from(&records, "textDocument/definition", result_set)[0]["inV"];
+    let items = edges_from(&records, "item", definition_result);
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["inVs"][0], range_at(&records, 1, 11)["id"]);
+
+    let reference_result = &edges_from(&records, "textDocument/references", result_set)[0]["inV"];
+    let properties: Vec<&Value> = edges_from(&records, "item", reference_result)
+        .iter()
+        .map(|item| &item["property"])
+        .collect();
+    assert_eq!(properties, vec!["definitions", "references"]);
+}
+
+#[test]
+fn test_positions_count_utf16_code_units() {
+    let temp_dir = TempDir::new().unwrap();
+    // The crab is one character but two UTF-16 code units
+    fs::write(temp_dir.path().join("lib.rs"), "const CRAB: &str = \"🦀\"; fn after() {}\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_verus-etags"))
+        .current_dir(temp_dir.path())
+        .args(["--format", "lsif", "-o", "-", "lib.rs"])
+        .output()
+        .expect("Failed to execute verus-etags");
+    assert!(output.status.success(), "verus-etags failed: {:?}", output);
+    let records: Vec<Value> = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("Each line should be JSON"))
+        .collect();
+
+    let after = range_at(&records, 0, 28);
+    assert_eq!(after["tag"]["text"], "after");
+    assert_eq!(after["end"]["character"], 33);
+}
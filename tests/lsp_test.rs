@@ -0,0 +1,199 @@
+use serde_json::{json, Value};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use tempfile::TempDir;
+
+fn frame(body: &str) -> String {
+    format!("Content-Length: {}\r\n\r\n{}", body.len(), body)
+}
+
+fn uri(path: &Path) -> String {
+    format!("file://{}", path.display())
+}
+
+/// Send `messages` followed by shutdown and exit, and return the responses
+/// by request id
+fn session(workspace: &Path, messages: Vec<Value>) -> Vec<Value> {
+    session_bodies(workspace, messages.iter().map(Value::to_string).collect())
+}
+
+/// Like `session`, with the message bodies as sent
+fn session_bodies(workspace: &Path, bodies: Vec<String>) -> Vec<Value> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_verus-etags"))
+        .current_dir(workspace)
+        .arg("lsp")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to execute verus-etags");
+
+    let mut input = frame(&json!({"jsonrpc": "2.0", "id": 0, "method": "initialize", "params": {}}).to_string());
+    for body in bodies {
+        input.push_str(&frame(&body));
+    }
+    input.push_str(&frame(&json!({"jsonrpc": "2.0", "id": 999, "method": "shutdown"}).to_string()));
+    input.push_str(&frame(&json!({"jsonrpc": "2.0", "method": "exit"}).to_string()));
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success(), "verus-etags lsp failed: {:?}", output);
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let mut responses = Vec::new();
+    let mut rest = stdout.as_str();
+    while let Some((header, body)) = rest.split_once("\r\n\r\n") {
+        let length: usize = header.trim_start_matches("Content-Length: ").parse().unwrap();
+        responses.push(serde_json::from_str(&body[..length]).unwrap());
+        rest = &body[length..];
+    }
+    responses
+}
+
+fn response(responses: &[Value], id: u64) -> &Value {
+    let response = responses.iter().find(|response| response["id"] == id).expect("No response");
+    &response["result"]
+}
+
+fn request(id: u64, method: &str, params: Value) -> Value {
+    json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params})
+}
+
+#[test]
+fn test_symbols_and_definition() {
+    let temp_dir = TempDir::new().unwrap();
+    let workspace = fs::canonicalize(temp_dir.path()).unwrap();
+    fs::write(workspace.join("lib.rs"), "mod shapes {\n    pub fn helper() {}\n}\n").unwrap();
+    fs::write(workspace.join("main.rs"), "fn main() {\n    helper();\n}\n").unwrap();
+    let lib = json!({"uri": uri(&workspace.join("lib.rs"))});
+    let main = json!({"uri": uri(&workspace.join("main.rs"))});
+
+    let responses = session(
+        &workspace,
+        vec![
+            request(1, "workspace/symbol", json!({"query": "HELP"})),
+            request(2, "textDocument/documentSymbol", json!({"textDocument": lib})),
+            request(3, "textDocument/definition", json!({"textDocument": main, "position": {"line": 1, "character": 6}})),
+        ],
+    );
+
+    let capabilities = &response(&responses, 0)["capabilities"];
+    assert_eq!(capabilities["definitionProvider"], true);
+
+    let symbols = response(&responses, 1).as_array().unwrap();
+    assert_eq!(symbols.len(), 1);
+    assert_eq!(symbols[0]["name"], "helper");
+    assert_eq!(symbols[0]["containerName"], "shapes");
+
+    let outline = response(&responses, 2);
+    assert_eq!(outline[0]["name"], "shapes");
+    assert_eq!(outline[0]["children"][0]["name"], "helper");
+
+    let definitions = response(&responses, 3).as_array().unwrap();
+    assert_eq!(definitions.len(), 1);
+    assert_eq!(definitions[0]["uri"], lib["uri"]);
+    assert_eq!(definitions[0]["range"]["start"], json!({"line": 1, "character": 11}));
+}
+
+#[test]
+fn test_did_change_retags_buffer() {
+    let temp_dir = TempDir::new().unwrap();
+    let workspace = fs::canonicalize(temp_dir.path()).unwrap();
+    fs::write(workspace.join("lib.rs"), "fn old_name() {}\n").unwrap();
+    let lib = uri(&workspace.join("lib.rs"));
+
+    let responses = session(
+        &workspace,
+        vec![
+            json!({"jsonrpc": "2.0", "method": "textDocument/didChange", "params": {
+                "textDocument": {"uri": lib, "version": 2},
+                "contentChanges": [{"text": "fn new_name() {}\n\nfn caller() {\n    new_name();\n}\n"}],
+            }}),
+            request(1, "workspace/symbol", json!({"query": "name"})),
+            request(2, "textDocument/references", json!({
+                "textDocument": {"uri": lib},
+                "position": {"line": 0, "character": 3},
+                "context": {"includeDeclaration": true},
+            })),
+        ],
+    );
+
+    let names: Vec<&Value> = response(&responses, 1).as_array().unwrap().iter().map(|symbol| &symbol["name"]).collect();
+    assert_eq!(names, vec!["new_name"]);
+
+    let lines: Vec<&Value> = response(&responses, 2)
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|location| &location["range"]["start"]["line"])
+        .collect();
+    assert_eq!(lines, vec![0, 3]);
+}
+
+#[test]
+fn test_document_symbols_on_one_line_are_siblings() {
+    let temp_dir = TempDir::new().unwrap();
+    let workspace = fs::canonicalize(temp_dir.path()).unwrap();
+    fs::write(workspace.join("lib.rs"), "enum Color { Red, Green, Blue }\n").unwrap();
+    let lib = json!({"uri": uri(&workspace.join("lib.rs"))});
+
+    let responses = session(&workspace, vec![request(1, "textDocument/documentSymbol", json!({"textDocument": lib}))]);
+
+    let outline = response(&responses, 1);
+    let variants: Vec<&Value> = outline[0]["children"].as_array().unwrap().iter().map(|symbol| &symbol["name"]).collect();
+    assert_eq!(variants, vec!["Color::Red", "Color::Green", "Color::Blue"]);
+}
+
+#[test]
+fn test_invalid_json_is_a_parse_error() {
+    let temp_dir = TempDir::new().unwrap();
+    let workspace = fs::canonicalize(temp_dir.path()).unwrap();
+    fs::write(workspace.join("lib.rs"), "fn helper() {}\n").unwrap();
+
+    let responses = session_bodies(
+        &workspace,
+        vec![
+            "{\"jsonrpc\": \"2.0\", \"id\": 1,".to_string(),
+            request(2, "workspace/symbol", json!({"query": "helper"})).to_string(),
+        ],
+    );
+
+    let error = responses.iter().find(|response| response["id"].is_null()).expect("No parse error");
+    assert_eq!(error["error"]["code"], -32700);
+    assert_eq!(response(&responses, 2).as_array().unwrap().len(), 1);
+}
+
+#[test]
+fn test_positions_count_utf16_code_units() {
+    let temp_dir = TempDir::new().unwrap();
+    let workspace = fs::canonicalize(temp_dir.path()).unwrap();
+    fs::write(
+        workspace.join("lib.rs"),
+        "/* \u{1F600} */ fn helper() {}\n\nfn main() {\n    helper();\n}\n",
+    )
+    .unwrap();
+    let lib = json!({"uri": uri(&workspace.join("lib.rs"))});
+
+    let responses = session(
+        &workspace,
+        vec![
+            // Just after `helper`: character 17, but 18 UTF-16 code units
+            request(1, "textDocument/references", json!({
+                "textDocument": lib,
+                "position": {"line": 0, "character": 18},
+                "context": {"includeDeclaration": true},
+            })),
+        ],
+    );
+
+    assert_eq!(response(&responses, 0)["capabilities"]["positionEncoding"], "utf-16");
+    let starts: Vec<&Value> = response(&responses, 1)
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|location| &location["range"]["start"])
+        .collect();
+    assert_eq!(starts, vec![&json!({"line": 0, "character": 12}), &json!({"line": 3, "character": 4})]);
+}
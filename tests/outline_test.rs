@@ -0,0 +1,128 @@
+use serde_json::Value;
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+fn outline(source: &str) -> Value {
+    let temp_dir = TempDir::new().unwrap();
+    let file = temp_dir.path().join("lib.rs");
+    fs::write(&file, source).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_verus-etags"))
+        .arg("outline")
+        .arg(&file)
+        .output()
+        .expect("Failed to execute verus-etags");
+    assert!(output.status.success(), "verus-etags failed: {:?}", output);
+    serde_json::from_slice(&output.stdout).expect("Output should be JSON")
+}
+
+fn names(nodes: &Value) -> Vec<&str> {
+    nodes.as_array().unwrap().iter().map(|node| node["name"].as_str().unwrap()).collect()
+}
+
+#[test]
+fn test_items_nest_in_their_containers() {
+    let tree = outline(
+        "mod shapes {
+    pub enum Shape {
+        Circle,
+        Square,
+    }
+
+    impl Shape {
+        pub fn area(&self) -> u32 {
+            0
+        }
+    }
+}
+
+fn main() {}
+",
+    );
+
+    assert_eq!(names(&tree), vec!["shapes", "main"]);
+    let shapes = &tree[0];
+    assert_eq!(shapes["kind"], "module");
+    assert_eq!(shapes["line"], 1);
+    assert_eq!(shapes["end_line"], 12);
+
+    let children = &shapes["children"];
+    assert_eq!(children[0]["kind"], "enum");
+    assert_eq!(names(&children[0]["children"]).len(), 2);
+    assert_eq!(children[1]["kind"], "impl");
+    assert_eq!(names(&children[1]["children"]), vec!["area"]);
+    assert_eq!(children[1]["children"][0]["children"], Value::Array(Vec::new()));
+}
+
+#[test]
+fn test_items_on_one_line_are_siblings() {
+    let tree = outline("enum Color { Red, Green, Blue }\n\nstruct S;\n\nimpl S { fn a() {} fn b() {} }\n");
+
+    assert_eq!(names(&tree), vec!["Color", "S", "impl S"]);
+    let variants = &tree[0]["children"];
+    assert_eq!(names(variants), vec!["Color::Red", "Color::Green", "Color::Blue"]);
+    assert!(variants.as_array().unwrap().iter().all(|variant| variant["children"] == Value::Array(Vec::new())));
+
+    let methods = &tree[2]["children"];
+    assert_eq!(names(methods), vec!["a", "b"]);
+    assert_eq!(methods[0]["children"], Value::Array(Vec::new()));
+}
+
+#[test]
+fn test_verus_items_nest_in_their_containers() {
+    let tree = outline(
+        "use vstd::prelude::*;
+
+verus! {
+
+mod m {
+    pub trait T {
+        spec fn f(&self) -> int;
+    }
+
+    pub struct S;
+
+    impl T for S {
+        spec fn f(&self) -> int {
+            0
+        }
+    }
+}
+
+} // verus!
+",
+    );
+
+    assert_eq!(names(&tree), vec!["m"]);
+    let items = &tree[0]["children"];
+    assert_eq!(names(items), vec!["T", "S", "impl T for S"]);
+    assert_eq!(names(&items[0]["children"]), vec!["f"]);
+    assert_eq!(names(&items[2]["children"]), vec!["f"]);
+    assert_eq!(items[2]["children"][0]["mode"], "spec");
+}
+
+#[test]
+fn test_directory_is_rejected() {
+    let temp_dir = TempDir::new().unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_verus-etags"))
+        .arg("outline")
+        .arg(temp_dir.path())
+        .output()
+        .expect("Failed to execute verus-etags");
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_missing_file_is_an_error() {
+    let temp_dir = TempDir::new().unwrap();
+    let missing = temp_dir.path().join("missing.rs");
+    let output = Command::new(env!("CARGO_BIN_EXE_verus-etags"))
+        .arg("outline")
+        .arg(&missing)
+        .output()
+        .expect("Failed to execute verus-etags");
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("missing.rs"));
+}
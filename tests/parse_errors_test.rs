@@ -0,0 +1,73 @@
+use std::fs;
+use std::path::Path;
+use std::process::Output;
+use tempfile::TempDir;
+
+mod common;
+
+fn make_sources(root: &Path) {
+    fs::write(root.join("good.rs"), "fn good_fn() {}\n").unwrap();
+    // Nothing in broken.rs can be recovered, so the whole file is skipped
+    fs::write(root.join("broken.rs"), "// broken\n\nstatic BROKEN: u32 = (1 {\n}\n").unwrap();
+}
+
+fn run_verus_etags(root: &Path, extra_args: &[&str]) -> Output {
+    common::verus_etags(root, &[&["-o", "TAGS"], extra_args, &["good.rs", "broken.rs"]].concat())
+}
+
+#[test]
+fn test_skipped_files_are_summarized() {
+    let temp_dir = TempDir::new().unwrap();
+    make_sources(temp_dir.path());
+
+    let output = run_verus_etags(temp_dir.path(), &[]);
+
+    assert!(output.status.success(), "Parse failures are not fatal by default");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("skipped 1 file(s)"), "stderr: {}", stderr);
+    assert!(stderr.contains("  broken.rs:3:"), "Should report the error line: {}", stderr);
+    assert!(fs::read_to_string(temp_dir.path().join("TAGS")).unwrap().contains("good_fn"));
+}
+
+#[test]
+fn test_no_summary_without_failures() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("good.rs"), "fn good_fn() {}\n").unwrap();
+
+    let output = common::verus_etags(temp_dir.path(), &["--strict", "good.rs"]);
+
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+}
+
+#[test]
+fn test_strict_exits_with_error() {
+    let temp_dir = TempDir::new().unwrap();
+    make_sources(temp_dir.path());
+
+    let output = run_verus_etags(temp_dir.path(), &["--strict"]);
+
+    assert!(!output.status.success(), "--strict should fail on parse errors");
+    let tags_content = fs::read_to_string(temp_dir.path().join("TAGS")).unwrap();
+    assert!(tags_content.contains("good_fn"), "Tags are still written with --strict");
+}
+
+#[test]
+fn test_error_format_json() {
+    let temp_dir = TempDir::new().unwrap();
+    make_sources(temp_dir.path());
+
+    let output = run_verus_etags(temp_dir.path(), &["--error-format", "json"]);
+
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let records: Vec<serde_json::Value> = stderr
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("Each line should be JSON"))
+        .collect();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0]["file"], "broken.rs");
+    assert_eq!(records[0]["line"], 3);
+    assert!(records[0]["column"].as_u64().unwrap() >= 1);
+    assert!(records[0]["message"].as_str().is_some());
+}
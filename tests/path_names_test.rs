@@ -0,0 +1,83 @@
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+mod common;
+
+// <root>/project/src/lib.rs and <root>/project/build/ for the tags file
+fn make_project() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    let project = temp_dir.path().join("project");
+    fs::create_dir_all(project.join("src")).unwrap();
+    fs::create_dir_all(project.join("build")).unwrap();
+    fs::write(project.join("src/lib.rs"), "pub fn lib_fn() {}\n").unwrap();
+    temp_dir
+}
+
+fn run_verus_etags(project: &Path, args: &[&str]) -> String {
+    let output = common::run_verus_etags(project, args);
+    String::from_utf8(output.stdout).unwrap()
+}
+
+fn section_names(tags_content: &str) -> Vec<String> {
+    tags_content
+        .split('\x0c')
+        .filter_map(|section| section.strip_prefix('\n'))
+        .filter_map(|section| section.lines().next())
+        .filter_map(|header| header.rsplit_once(',').map(|(name, _)| name.to_string()))
+        .collect()
+}
+
+#[test]
+fn test_names_are_normalized() {
+    let temp_dir = make_project();
+    let project = temp_dir.path().join("project");
+
+    let tags_content = run_verus_etags(&project, &["-o", "-", "./build/../src/./lib.rs"]);
+    assert_eq!(section_names(&tags_content), vec!["src/lib.rs"]);
+}
+
+#[test]
+fn test_tag_relative() {
+    let temp_dir = make_project();
+    let project = temp_dir.path().join("project");
+
+    run_verus_etags(&project, &["-o", "build/TAGS", "--tag-relative", "src/lib.rs"]);
+    let tags_content = fs::read_to_string(project.join("build/TAGS")).unwrap();
+    assert_eq!(section_names(&tags_content), vec!["../src/lib.rs"]);
+}
+
+#[test]
+fn test_absolute() {
+    let temp_dir = make_project();
+    let project = temp_dir.path().join("project");
+    let cwd = project.canonicalize().unwrap();
+
+    // Symlinks are not resolved, so compare against the canonical working directory
+    let output = common::run_verus_etags(&cwd, &["-o", "-", "--absolute", "src/lib.rs"]);
+
+    let tags_content = String::from_utf8(output.stdout).unwrap();
+    let expected = cwd.join("src/lib.rs").display().to_string();
+    assert_eq!(section_names(&tags_content), vec![expected]);
+}
+
+#[test]
+fn test_path_map() {
+    let temp_dir = make_project();
+    let project = temp_dir.path().join("project");
+
+    let tags_content = run_verus_etags(
+        &project,
+        &["-o", "-", "--path-map", "src=/workspace/src", "--path-map", "src=/unused", "src/lib.rs"],
+    );
+    assert_eq!(section_names(&tags_content), vec!["/workspace/src/lib.rs"]);
+}
+
+#[test]
+fn test_path_map_requires_separator() {
+    let temp_dir = make_project();
+    let project = temp_dir.path().join("project");
+
+    let output = common::verus_etags(&project, &["--path-map", "no-separator", "src/lib.rs"]);
+    assert!(!output.status.success());
+}
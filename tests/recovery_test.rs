@@ -0,0 +1,92 @@
+use std::fs;
+use std::path::Path;
+use std::process::Output;
+use tempfile::TempDir;
+
+mod common;
+
+fn run_verus_etags(root: &Path, args: &[&str]) -> Output {
+    common::verus_etags(root, &[&["-o", "TAGS"], args].concat())
+}
+
+fn tag_file(source: &str) -> (String, String) {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("partial.rs"), source).unwrap();
+
+    let output = run_verus_etags(temp_dir.path(), &["partial.rs"]);
+    assert!(output.status.success(), "verus-etags failed: {:?}", output);
+
+    let tags_content = fs::read_to_string(temp_dir.path().join("TAGS")).unwrap();
+    (tags_content, String::from_utf8_lossy(&output.stderr).into_owned())
+}
+
+#[test]
+fn test_broken_item_does_not_hide_its_neighbours() {
+    let source = "\
+fn before() {}
+
+fn broken() -> {
+    1
+}
+
+struct After {
+    field: u32,
+}
+";
+    let (tags_content, stderr) = tag_file(source);
+
+    assert!(tags_content.contains("before\x011,"), "TAGS: {}", tags_content);
+    assert!(tags_content.contains("After\x017,"), "TAGS: {}", tags_content);
+    assert!(stderr.contains("tagged 1 file(s) despite parse errors"), "stderr: {}", stderr);
+    assert!(stderr.contains("items skipped at lines 3"), "stderr: {}", stderr);
+}
+
+#[test]
+fn test_unbalanced_brace_costs_one_region() {
+    let source = "\
+fn first() {}
+
+fn unclosed() {
+    if true {
+
+fn last() {}
+";
+    let (tags_content, _) = tag_file(source);
+
+    assert!(tags_content.contains("first\x011,"), "TAGS: {}", tags_content);
+    assert!(tags_content.contains("unclosed\x013,"), "The keyword scanner finds it: {}", tags_content);
+    assert!(tags_content.contains("last\x016,"), "TAGS: {}", tags_content);
+}
+
+#[test]
+fn test_broken_item_inside_verus_macro() {
+    let source = "\
+verus! {
+fn a() {}
+fn b() -> {}
+fn c() {}
+}
+";
+    let (tags_content, stderr) = tag_file(source);
+
+    assert!(tags_content.contains("a\x012,"), "TAGS: {}", tags_content);
+    assert!(tags_content.contains("c\x014,"), "TAGS: {}", tags_content);
+    assert!(stderr.contains("partial.rs:3:"), "stderr: {}", stderr);
+    assert!(stderr.contains("items skipped at lines 3)"), "stderr: {}", stderr);
+}
+
+#[test]
+fn test_recovered_files_fail_strict() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("partial.rs"), "fn good() {}\nfn bad( {}\n").unwrap();
+
+    let output = run_verus_etags(temp_dir.path(), &["--strict", "--error-format", "json", "partial.rs"]);
+
+    assert!(!output.status.success(), "--strict should fail on recovered files");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let record: serde_json::Value =
+        serde_json::from_str(stderr.lines().next().unwrap()).expect("First line should be JSON");
+    assert_eq!(record["recovered"], true);
+    assert_eq!(record["unparsed_lines"], serde_json::json!([2]));
+    assert!(fs::read_to_string(temp_dir.path().join("TAGS")).unwrap().contains("good"));
+}
@@ -0,0 +1,129 @@
+use rusqlite::Connection;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+mod common;
+
+fn run_verus_etags(root: &Path, args: &[&str]) {
+    common::run_verus_etags(root, &[&["--format", "sqlite", "-o", "tags.db"], args].concat());
+}
+
+fn names(connection: &Connection, sql: &str) -> Vec<String> {
+    let mut statement = connection.prepare(sql).unwrap();
+    let rows = statement.query_map([], |row| row.get(0)).unwrap();
+    rows.map(Result::unwrap).collect()
+}
+
+fn make_sources(root: &Path) {
+    fs::write(root.join("a.rs"), "fn helper() {}\n\nfn caller() {\n    helper();\n}\n").unwrap();
+    fs::write(root.join("b.rs"), "struct Point {\n    x: u32,\n}\n").unwrap();
+}
+
+#[test]
+fn test_tags_files_and_references() {
+    let temp_dir = TempDir::new().unwrap();
+    make_sources(temp_dir.path());
+    run_verus_etags(temp_dir.path(), &["a.rs", "b.rs"]);
+
+    let connection = Connection::open(temp_dir.path().join("tags.db")).unwrap();
+    assert_eq!(names(&connection, "SELECT path FROM files ORDER BY path"), vec!["a.rs", "b.rs"]);
+    assert_eq!(
+        names(
+            &connection,
+            "SELECT tags.name FROM tags JOIN files ON files.id = tags.file_id
+             WHERE files.path = 'a.rs' AND tags.kind = 'function' ORDER BY tags.line"
+        ),
+        vec!["helper", "caller"]
+    );
+
+    let (kind, line, end_line): (String, i64, i64) = connection
+        .query_row("SELECT kind, line, end_line FROM tags WHERE name = 'Point'", [], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })
+        .unwrap();
+    assert_eq!((kind.as_str(), line, end_line), ("struct", 1, 3));
+
+    let (line, enclosing_fn): (i64, String) = connection
+        .query_row("SELECT line, enclosing_fn FROM refs WHERE name = 'helper'", [], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })
+        .unwrap();
+    assert_eq!((line, enclosing_fn.as_str()), (4, "caller"));
+
+    let indices = names(&connection, "SELECT name FROM sqlite_master WHERE type = 'index' ORDER BY name");
+    for index in ["refs_file", "refs_name", "tags_file", "tags_name"] {
+        assert!(indices.iter().any(|name| name == index), "Missing index {}: {:?}", index, indices);
+    }
+}
+
+#[test]
+fn test_append_replaces_only_changed_files() {
+    let temp_dir = TempDir::new().unwrap();
+    make_sources(temp_dir.path());
+    run_verus_etags(temp_dir.path(), &["a.rs", "b.rs"]);
+
+    fs::write(temp_dir.path().join("a.rs"), "fn renamed() {}\n").unwrap();
+    run_verus_etags(temp_dir.path(), &["--append", "a.rs"]);
+
+    let connection = Connection::open(temp_dir.path().join("tags.db")).unwrap();
+    assert_eq!(names(&connection, "SELECT name FROM tags ORDER BY name"), vec!["Point", "renamed"]);
+    assert!(names(&connection, "SELECT name FROM refs").is_empty(), "Old references are removed");
+    assert_eq!(names(&connection, "SELECT path FROM files ORDER BY path"), vec!["a.rs", "b.rs"]);
+}
+
+#[test]
+fn test_without_append_database_is_rebuilt() {
+    let temp_dir = TempDir::new().unwrap();
+    make_sources(temp_dir.path());
+    run_verus_etags(temp_dir.path(), &["a.rs", "b.rs"]);
+    run_verus_etags(temp_dir.path(), &["b.rs"]);
+
+    let connection = Connection::open(temp_dir.path().join("tags.db")).unwrap();
+    assert_eq!(names(&connection, "SELECT path FROM files"), vec!["b.rs"]);
+}
+
+#[test]
+fn test_append_to_older_schema_rebuilds_database() {
+    let temp_dir = TempDir::new().unwrap();
+    make_sources(temp_dir.path());
+    {
+        // A database from before tags had item ranges, with no schema version
+        let connection = Connection::open(temp_dir.path().join("tags.db")).unwrap();
+        connection
+            .execute_batch(
+                "CREATE TABLE files (id INTEGER PRIMARY KEY, path TEXT NOT NULL UNIQUE);
+                 CREATE TABLE tags (id INTEGER PRIMARY KEY, file_id INTEGER NOT NULL, name TEXT NOT NULL);
+                 INSERT INTO files (path) VALUES ('old.rs');",
+            )
+            .unwrap();
+    }
+    run_verus_etags(temp_dir.path(), &["--append", "a.rs"]);
+
+    let connection = Connection::open(temp_dir.path().join("tags.db")).unwrap();
+    assert_eq!(names(&connection, "SELECT path FROM files"), vec!["a.rs"]);
+    assert_eq!(
+        names(&connection, "SELECT name FROM tags WHERE end_line = 1 ORDER BY name"),
+        vec!["helper"]
+    );
+    let version: i64 = connection.query_row("PRAGMA user_version", [], |row| row.get(0)).unwrap();
+    assert!(version > 0);
+}
+
+#[test]
+fn test_append_drops_deleted_and_untaggable_files() {
+    let temp_dir = TempDir::new().unwrap();
+    make_sources(temp_dir.path());
+    fs::write(temp_dir.path().join("c.rs"), "fn kept() {}\n").unwrap();
+    run_verus_etags(temp_dir.path(), &["a.rs", "b.rs", "c.rs"]);
+
+    fs::remove_file(temp_dir.path().join("b.rs")).unwrap();
+    fs::write(temp_dir.path().join("a.rs"), "}}}\n").unwrap();
+    fs::write(temp_dir.path().join("changed.txt"), "a.rs\nb.rs\n").unwrap();
+    run_verus_etags(temp_dir.path(), &["--append", "-L", "changed.txt"]);
+
+    let connection = Connection::open(temp_dir.path().join("tags.db")).unwrap();
+    assert_eq!(names(&connection, "SELECT path FROM files"), vec!["c.rs"]);
+    assert_eq!(names(&connection, "SELECT name FROM tags"), vec!["kept"]);
+    assert!(names(&connection, "SELECT name FROM refs").is_empty(), "a.rs references are removed");
+}
@@ -0,0 +1,137 @@
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+const STATS_SOURCE: &str = r#"verus! {
+
+spec fn double(x: int) -> int {
+    x + x
+}
+
+proof fn lemma_double(x: int)
+    ensures double(x) == 2 * x,
+{
+}
+
+fn exec_double(x: u32) -> (r: u32)
+    requires x < 1000,
+{
+    proof {
+        lemma_double(x as int);
+    }
+    x * 2
+}
+
+#[verifier::external_body]
+fn trusted_io() {
+}
+
+mod inner {
+    proof fn lemma_inner() {
+    }
+}
+
+}
+"#;
+
+fn run_stats(temp_dir: &TempDir, extra_args: &[&str]) -> String {
+    let test_file = temp_dir.path().join("stats.rs");
+    fs::write(&test_file, STATS_SOURCE).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_verus-etags"))
+        .arg("stats")
+        .args(extra_args)
+        .arg(&test_file)
+        .output()
+        .expect("Failed to execute verus-etags");
+
+    assert!(output.status.success(), "verus-etags failed: {:?}", output);
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+#[test]
+fn test_stats_csv() {
+    let temp_dir = TempDir::new().unwrap();
+    let csv = run_stats(&temp_dir, &["--csv"]);
+    let lines: Vec<&str> = csv.lines().collect();
+
+    assert_eq!(
+        lines[0],
+        "file,module,spec_fns,proof_fns,exec_fns,spec_lines,proof_lines,exec_lines,proof_block_lines,spec_clause_lines,trusted_items,ghost_exec_ratio"
+    );
+
+    // Whole file: 1 spec fn (3 lines), 2 proof fns (4 + 2 lines), 2 exec fns (8 + 3 lines),
+    // one 3-line proof block, two 1-line spec clauses, one external_body fn.
+    // Ghost lines are 3 + 6 + the 4 in exec_double, per 11 - 4 exec lines.
+    let file_row = lines[1];
+    assert!(file_row.ends_with("stats.rs,,1,2,2,3,6,11,3,2,1,1.86"), "Unexpected file row: {}", file_row);
+
+    // Inline module row
+    let module_row = lines[2];
+    assert!(module_row.ends_with("stats.rs,inner,0,1,0,0,2,0,0,0,0,"), "Unexpected module row: {}", module_row);
+
+    assert_eq!(lines[3], "TOTAL,,1,2,2,3,6,11,3,2,1,1.86");
+}
+
+#[test]
+fn test_stats_table() {
+    let temp_dir = TempDir::new().unwrap();
+    let table = run_stats(&temp_dir, &[]);
+
+    assert!(table.starts_with("FILE"), "Missing table header: {}", table);
+    assert!(table.contains("  mod inner"), "Missing module row: {}", table);
+    assert!(table.lines().last().unwrap().starts_with("TOTAL"), "Missing total row: {}", table);
+}
+
+#[test]
+fn test_stats_does_not_write_tags() {
+    let temp_dir = TempDir::new().unwrap();
+    let test_file = temp_dir.path().join("stats.rs");
+    fs::write(&test_file, STATS_SOURCE).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_verus-etags"))
+        .current_dir(temp_dir.path())
+        .arg("stats")
+        .arg("stats.rs")
+        .output()
+        .expect("Failed to execute verus-etags");
+
+    assert!(output.status.success(), "verus-etags failed: {:?}", output);
+    assert!(!temp_dir.path().join("TAGS").exists(), "stats should not write TAGS");
+}
+
+#[test]
+fn test_stats_rows_are_sorted_and_deduplicated() {
+    let temp_dir = TempDir::new().unwrap();
+    for name in ["zeta.rs", "alpha.rs", "mid.rs"] {
+        fs::write(temp_dir.path().join(name), "fn f() {}\n").unwrap();
+    }
+
+    // The same file named twice, once through a different path
+    let output = Command::new(env!("CARGO_BIN_EXE_verus-etags"))
+        .current_dir(temp_dir.path())
+        .args(["stats", "--csv", ".", "./alpha.rs"])
+        .output()
+        .expect("Failed to execute verus-etags");
+    assert!(output.status.success(), "verus-etags failed: {:?}", output);
+
+    let csv = String::from_utf8(output.stdout).unwrap();
+    let files: Vec<&str> = csv.lines().skip(1).map(|line| line.split(',').next().unwrap()).collect();
+    assert_eq!(files, vec!["./alpha.rs", "./mid.rs", "./zeta.rs", "TOTAL"]);
+}
+
+#[test]
+fn test_stats_csv_quotes_fields() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("a,b.rs"), "fn f() {}\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_verus-etags"))
+        .current_dir(temp_dir.path())
+        .args(["stats", "--csv", "a,b.rs"])
+        .output()
+        .expect("Failed to execute verus-etags");
+    assert!(output.status.success(), "verus-etags failed: {:?}", output);
+
+    let csv = String::from_utf8(output.stdout).unwrap();
+    assert!(csv.lines().nth(1).unwrap().starts_with("\"a,b.rs\",,"), "CSV: {}", csv);
+}
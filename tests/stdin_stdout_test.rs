@@ -0,0 +1,59 @@
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+use tempfile::TempDir;
+
+fn run_with_stdin(root: &Path, args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_verus-etags"))
+        .current_dir(root)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to execute verus-etags");
+    child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn test_stdin_source_to_stdout() {
+    let temp_dir = TempDir::new().unwrap();
+    let source = "verus! {\n    fn unsaved_fn() -> bool { true }\n}\n";
+
+    let output = run_with_stdin(
+        temp_dir.path(),
+        &["-o", "-", "--stdin-filename", "src/buffer.rs", "-"],
+        source,
+    );
+
+    assert!(output.status.success(), "verus-etags failed: {:?}", output);
+    let tags_content = String::from_utf8(output.stdout).unwrap();
+    assert!(tags_content.starts_with("\x0c\nsrc/buffer.rs,"), "Unexpected header: {:?}", tags_content);
+    assert!(tags_content.contains("\x7funsaved_fn\x012,"));
+    assert!(!temp_dir.path().join("-").exists(), "Should not create a file named -");
+}
+
+#[test]
+fn test_stdin_source_mixed_with_files() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("disk.rs"), "fn on_disk() {}\n").unwrap();
+
+    let output = run_with_stdin(temp_dir.path(), &["-o", "TAGS", "disk.rs", "-"], "fn from_stdin() {}\n");
+
+    assert!(output.status.success(), "verus-etags failed: {:?}", output);
+    let tags_content = fs::read_to_string(temp_dir.path().join("TAGS")).unwrap();
+    assert!(tags_content.contains("\x0c\ndisk.rs,"));
+    assert!(tags_content.contains("\x0c\n-,"), "Stdin defaults to the name -");
+    assert!(tags_content.contains("from_stdin"));
+}
+
+#[test]
+fn test_stdin_cannot_be_used_twice() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let output = run_with_stdin(temp_dir.path(), &["-L", "-", "-"], "");
+
+    assert!(!output.status.success());
+}
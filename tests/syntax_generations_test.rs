@@ -0,0 +1,96 @@
+use std::fs;
+use tempfile::TempDir;
+
+mod common;
+
+// `axiom fn` is newer than the bundled verus_syn; it is tagged as a `proof fn`
+const SOURCE: &str = "\
+fn plain() {}
+
+axiom fn lemma_admitted() {}
+
+verus! {
+fn in_body() {}
+axiom fn lemma_in_body() {}
+}
+";
+
+fn run_verus_etags(temp_dir: &TempDir, extra_args: &[&str]) -> (String, String) {
+    fs::write(temp_dir.path().join("newer.rs"), SOURCE).unwrap();
+    let output = common::run_verus_etags(temp_dir.path(), &[&["-o", "TAGS"], extra_args, &["newer.rs"]].concat());
+
+    let tags_content = fs::read_to_string(temp_dir.path().join("TAGS")).unwrap();
+    (tags_content, String::from_utf8_lossy(&output.stderr).into_owned())
+}
+
+#[test]
+fn test_newer_syntax_keeps_verus_tags() {
+    let temp_dir = TempDir::new().unwrap();
+    let (tags_content, stderr) = run_verus_etags(&temp_dir, &[]);
+
+    assert!(tags_content.contains("plain\x011,"), "TAGS: {}", tags_content);
+    assert!(tags_content.contains("lemma_admitted\x013,"), "TAGS: {}", tags_content);
+    assert!(tags_content.contains("in_body\x016,"), "verus! tags are kept: {}", tags_content);
+    assert!(tags_content.contains("lemma_in_body\x017,"), "TAGS: {}", tags_content);
+    assert!(stderr.contains("tagged 1 file(s) despite parse errors"), "stderr: {}", stderr);
+    assert!(
+        stderr.contains("note: `axiom fn` needs Verus 0.2025.06 or later, newer than the bundled verus_syn"),
+        "stderr: {}",
+        stderr
+    );
+    assert!(!stderr.contains("items skipped"), "Nothing was skipped: {}", stderr);
+}
+
+#[test]
+fn test_generation_in_json() {
+    let temp_dir = TempDir::new().unwrap();
+    let (_, stderr) = run_verus_etags(&temp_dir, &["--error-format", "json"]);
+
+    let record: serde_json::Value = serde_json::from_str(stderr.lines().next().unwrap()).unwrap();
+    assert_eq!(record["file"], "newer.rs");
+    assert_eq!(record["recovered"], true);
+    assert_eq!(
+        record["syntax"],
+        serde_json::json!([{"construct": "axiom fn", "generation": "newer", "release": "0.2025.06"}])
+    );
+}
+
+/// Tag `source` and return stderr, which has a note for every construct
+/// the file could only be parsed without
+fn notes(source: &str) -> String {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("lib.rs"), source).unwrap();
+    let output = common::run_verus_etags(temp_dir.path(), &["-o", "TAGS", "lib.rs"]);
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn test_uninterp_needs_stripping() {
+    let stderr = notes("verus! {\nuninterp spec fn f(x: int) -> int;\n}\n");
+    assert!(stderr.contains("note: `uninterp spec fn` needs Verus 0.2025.02 or later"), "stderr: {}", stderr);
+}
+
+#[test]
+fn test_syntax_the_bundled_parser_accepts_is_not_reported() {
+    let stderr = notes(
+        "verus! {\nspec(checked) fn f(x: int) -> int { x }\n\npub broadcast proof fn g(x: int) ensures f(x) == x {}\n\nbroadcast group gs { g }\n}\n",
+    );
+    assert!(stderr.is_empty(), "stderr: {}", stderr);
+}
+
+#[test]
+fn test_axiom_fn_is_a_proof_fn() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("newer.rs"), SOURCE).unwrap();
+    common::run_verus_etags(temp_dir.path(), &["--format", "json", "-o", "tags.json", "newer.rs"]);
+
+    let tags: Vec<serde_json::Value> = fs::read_to_string(temp_dir.path().join("tags.json"))
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    for name in ["lemma_admitted", "lemma_in_body"] {
+        let tag = tags.iter().find(|tag| tag["name"] == name).expect("Missing tag");
+        assert_eq!(tag["mode"], "proof", "{}", name);
+    }
+}
@@ -0,0 +1,154 @@
+#![cfg(unix)]
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::process::{Command, Output};
+use tempfile::TempDir;
+
+mod common;
+use common::write;
+
+// A sysroot with a tiny rust-src and a fake rustc that reports it
+fn make_toolchain(root: &Path) {
+    let library = root.join("sysroot/lib/rustlib/src/rust/library");
+    for krate in ["core", "alloc", "std"] {
+        write(
+            &library,
+            &format!("{}/Cargo.toml", krate),
+            &format!("[package]\nname = \"{}\"\nversion = \"0.0.0\"\n", krate),
+        );
+    }
+    write(&library, "core/src/lib.rs", "pub mod option;\n");
+    write(&library, "core/src/option.rs", "pub enum Option<T> { None, Some(T) }\n");
+    write(&library, "alloc/src/lib.rs", "pub struct Vec<T> { ptr: *mut T }\n");
+    write(&library, "std/src/lib.rs", "pub fn std_only_fn() {}\n");
+
+    let rustc = root.join("rustc");
+    fs::write(
+        &rustc,
+        format!(
+            "#!/bin/sh\npwd > \"$0.cwd\"\nif [ \"$1\" = \"--print\" ]; then echo '{}'; else printf 'rustc 1.80.0\\nrelease: 1.80.0\\ncommit-hash: 0123456789abcdef\\n'; fi\n",
+            root.join("sysroot").display()
+        ),
+    )
+    .unwrap();
+    fs::set_permissions(&rustc, fs::Permissions::from_mode(0o755)).unwrap();
+}
+
+fn run_verus_etags(root: &Path) -> Output {
+    let output = Command::new(env!("CARGO_BIN_EXE_verus-etags"))
+        .current_dir(root.join("project"))
+        .env("RUSTC", root.join("rustc"))
+        .env("XDG_CACHE_HOME", root.join("cache"))
+        .args(["-o", "TAGS", "--sysroot-std", "lib.rs"])
+        .output()
+        .expect("Failed to execute verus-etags");
+    assert!(output.status.success(), "verus-etags failed: {:?}", output);
+    output
+}
+
+#[test]
+fn test_sysroot_std_builds_cache_and_includes_it() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    make_toolchain(root);
+    write(root, "project/lib.rs", "pub fn project_fn() {}\n");
+
+    run_verus_etags(root);
+
+    let cache_path = root.join("cache/verus-etags/TAGS.std-1.80.0-0123456789ab-s1");
+    let std_tags = fs::read_to_string(&cache_path).unwrap();
+    assert!(std_tags.contains("\x7fOption\x01"));
+    assert!(std_tags.contains("\x7fOption::Some\x01"));
+    assert!(std_tags.contains("\x7fVec\x01"));
+    assert!(std_tags.contains("\x7fstd_only_fn\x01"));
+
+    let main_tags = fs::read_to_string(root.join("project/TAGS")).unwrap();
+    assert!(main_tags.contains("project_fn"));
+    assert!(
+        main_tags.contains(&format!("\x0c\n{},include\n", cache_path.display())),
+        "Missing include line: {:?}",
+        main_tags
+    );
+}
+
+#[test]
+fn test_sysroot_std_reuses_cache() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    make_toolchain(root);
+    write(root, "project/lib.rs", "pub fn project_fn() {}\n");
+
+    run_verus_etags(root);
+    let cache_path = root.join("cache/verus-etags/TAGS.std-1.80.0-0123456789ab-s1");
+    fs::write(&cache_path, "cached").unwrap();
+    run_verus_etags(root);
+
+    assert_eq!(fs::read_to_string(&cache_path).unwrap(), "cached");
+}
+
+#[test]
+fn test_sysroot_std_without_rust_src() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    make_toolchain(root);
+    fs::remove_dir_all(root.join("sysroot/lib/rustlib/src")).unwrap();
+    write(root, "project/lib.rs", "pub fn project_fn() {}\n");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_verus-etags"))
+        .current_dir(root.join("project"))
+        .env("RUSTC", root.join("rustc"))
+        .env("XDG_CACHE_HOME", root.join("cache"))
+        .args(["-o", "TAGS", "--sysroot-std", "lib.rs"])
+        .output()
+        .expect("Failed to execute verus-etags");
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("rustup component add rust-src"));
+}
+
+#[test]
+fn test_sysroot_std_cache_per_sort_mode() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    make_toolchain(root);
+    write(root, "project/lib.rs", "pub fn project_fn() {}\n");
+
+    run_verus_etags(root);
+    let output = Command::new(env!("CARGO_BIN_EXE_verus-etags"))
+        .current_dir(root.join("project"))
+        .env("RUSTC", root.join("rustc"))
+        .env("XDG_CACHE_HOME", root.join("cache"))
+        .args(["-o", "TAGS", "-s", "0", "--sysroot-std", "lib.rs"])
+        .output()
+        .expect("Failed to execute verus-etags");
+    assert!(output.status.success(), "verus-etags failed: {:?}", output);
+
+    assert!(root.join("cache/verus-etags/TAGS.std-1.80.0-0123456789ab-s1").exists());
+    assert!(root.join("cache/verus-etags/TAGS.std-1.80.0-0123456789ab-s0").exists());
+}
+
+#[test]
+fn test_sysroot_std_asks_rustc_from_the_project() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    make_toolchain(root);
+    write(root, "project/lib.rs", "pub fn project_fn() {}\n");
+
+    // Run from outside the project, naming its file
+    let output = Command::new(env!("CARGO_BIN_EXE_verus-etags"))
+        .current_dir(root)
+        .env("RUSTC", root.join("rustc"))
+        .env("XDG_CACHE_HOME", root.join("cache"))
+        .args(["-o", "TAGS", "--sysroot-std", "project/lib.rs"])
+        .output()
+        .expect("Failed to execute verus-etags");
+    assert!(output.status.success(), "verus-etags failed: {:?}", output);
+
+    let cwd = fs::read_to_string(root.join("rustc.cwd")).unwrap();
+    assert_eq!(
+        fs::canonicalize(cwd.trim()).unwrap(),
+        fs::canonicalize(root.join("project")).unwrap()
+    );
+}
@@ -0,0 +1,67 @@
+use std::fs;
+use tempfile::TempDir;
+
+mod common;
+
+const TRIGGER_SOURCE: &str = r#"
+verus! {
+
+spec fn f(x: int) -> int;
+
+spec fn g(x: int) -> int;
+
+proof fn lemma_inline_trigger()
+    requires forall|x: int| #[trigger] f(x) > 0,
+{
+}
+
+proof fn lemma_explicit_triggers()
+    requires forall|x: int, y: int| #![trigger f(x), g(y)] f(x) == g(y),
+{
+}
+
+}
+"#;
+
+fn run_verus_etags(temp_dir: &TempDir, extra_args: &[&str]) -> String {
+    fs::write(temp_dir.path().join("triggers.rs"), TRIGGER_SOURCE).unwrap();
+    common::run_verus_etags(temp_dir.path(), &[&["-o", "TAGS"], extra_args, &["triggers.rs"]].concat());
+    fs::read_to_string(temp_dir.path().join("TAGS")).unwrap()
+}
+
+#[test]
+fn test_trigger_tags_are_opt_in() {
+    let temp_dir = TempDir::new().unwrap();
+    let tags_content = run_verus_etags(&temp_dir, &[]);
+    assert!(!tags_content.contains("trigger:"), "Trigger tags should require --trigger-tags");
+}
+
+#[test]
+fn test_trigger_tags() {
+    let temp_dir = TempDir::new().unwrap();
+    let tags_content = run_verus_etags(&temp_dir, &["--trigger-tags"]);
+
+    // One #[trigger] f(x) and one #![trigger f(x), g(y)]
+    assert_eq!(tags_content.matches("\x7ftrigger:f\x01").count(), 2);
+    assert_eq!(tags_content.matches("\x7ftrigger:g\x01").count(), 1);
+}
+
+#[test]
+fn test_trigger_json() {
+    let temp_dir = TempDir::new().unwrap();
+    let json_file = temp_dir.path().join("triggers.json");
+    run_verus_etags(&temp_dir, &["--trigger-json", json_file.to_str().unwrap()]);
+
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&json_file).unwrap()).unwrap();
+
+    let f_triggers = report["triggers"]["f"].as_array().unwrap();
+    assert_eq!(f_triggers.len(), 2);
+    assert_eq!(f_triggers[0]["enclosing"], "lemma_inline_trigger");
+    assert_eq!(f_triggers[0]["quantifier"], "forall");
+    assert_eq!(f_triggers[1]["enclosing"], "lemma_explicit_triggers");
+
+    let g_triggers = report["triggers"]["g"].as_array().unwrap();
+    assert_eq!(g_triggers.len(), 1);
+    assert_eq!(g_triggers[0]["enclosing"], "lemma_explicit_triggers");
+}
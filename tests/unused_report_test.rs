@@ -0,0 +1,192 @@
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+const UNUSED_SOURCE: &str = r#"
+verus! {
+
+spec fn used_spec(x: int) -> int {
+    x + 1
+}
+
+spec fn dead_spec(x: int) -> int {
+    x + 2
+}
+
+spec fn recursive_dead(n: nat) -> nat
+    decreases n,
+{
+    if n == 0 { 0 } else { recursive_dead((n - 1) as nat) }
+}
+
+#[verifier::opaque]
+spec fn revealed_spec(x: int) -> int {
+    x
+}
+
+proof fn lemma_called()
+    ensures used_spec(0) == 1,
+{
+    reveal(revealed_spec);
+}
+
+proof fn lemma_abandoned() {
+}
+
+pub broadcast proof fn lemma_in_group(x: int)
+    ensures #[trigger] used_spec(x) == x + 1,
+{
+}
+
+pub broadcast group group_used {
+    lemma_in_group,
+}
+
+pub broadcast proof fn lemma_broadcast_used(x: int)
+    ensures #[trigger] used_spec(x) > x,
+{
+}
+
+fn main() {
+    proof {
+        lemma_called();
+        broadcast use group_used, lemma_broadcast_used;
+    }
+}
+
+}
+"#;
+
+#[test]
+fn test_unused_report() {
+    let temp_dir = TempDir::new().unwrap();
+    let test_file = temp_dir.path().join("unused.rs");
+    fs::write(&test_file, UNUSED_SOURCE).unwrap();
+    let tags_file = temp_dir.path().join("TAGS");
+    let report_file = temp_dir.path().join("unused.txt");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_verus-etags"))
+        .arg("-o")
+        .arg(&tags_file)
+        .arg("--unused-report")
+        .arg(&report_file)
+        .arg(&test_file)
+        .output()
+        .expect("Failed to execute verus-etags");
+
+    assert!(output.status.success(), "verus-etags failed: {:?}", output);
+
+    let report = fs::read_to_string(&report_file).unwrap();
+    assert!(report.contains(": unused spec fn dead_spec"), "dead_spec should be reported: {}", report);
+    assert!(report.contains(": unused spec fn recursive_dead"), "Self-recursion is not a use: {}", report);
+    assert!(report.contains(": unused proof fn lemma_abandoned"), "lemma_abandoned should be reported: {}", report);
+
+    // Direct calls, reveal, broadcast group membership and broadcast use all count
+    assert!(!report.contains("used_spec\n"), "used_spec is called: {}", report);
+    assert!(!report.contains("revealed_spec"), "revealed_spec is revealed: {}", report);
+    assert!(!report.contains("lemma_called"), "lemma_called is called: {}", report);
+    assert!(!report.contains("lemma_in_group"), "lemma_in_group is a group member: {}", report);
+    assert!(!report.contains("lemma_broadcast_used"), "lemma_broadcast_used is broadcast used: {}", report);
+
+    // Exec functions are never reported
+    assert!(!report.contains("fn main"), "exec fns are not reported: {}", report);
+}
+
+#[test]
+fn test_view_through_at_is_a_use() {
+    let temp_dir = TempDir::new().unwrap();
+    let test_file = temp_dir.path().join("view.rs");
+    fs::write(
+        &test_file,
+        r#"
+verus! {
+
+pub struct Counter {
+    pub n: u64,
+}
+
+impl View for Counter {
+    type V = nat;
+
+    closed spec fn view(&self) -> nat {
+        self.n as nat
+    }
+}
+
+proof fn lemma_counter(c: Counter)
+    ensures c@ == c.n as nat,
+{
+}
+
+fn main() {
+    let c = Counter { n: 0 };
+    proof {
+        lemma_counter(c);
+    }
+}
+
+}
+"#,
+    )
+    .unwrap();
+    let report_file = temp_dir.path().join("unused.txt");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_verus-etags"))
+        .arg("-o")
+        .arg(temp_dir.path().join("TAGS"))
+        .arg("--unused-report")
+        .arg(&report_file)
+        .arg(&test_file)
+        .output()
+        .expect("Failed to execute verus-etags");
+    assert!(output.status.success(), "verus-etags failed: {:?}", output);
+
+    let report = fs::read_to_string(&report_file).unwrap();
+    assert!(!report.contains("fn view"), "view is used through @: {}", report);
+}
+
+#[test]
+fn test_assert_by_requires_is_a_use() {
+    let temp_dir = TempDir::new().unwrap();
+    let test_file = temp_dir.path().join("assert_by.rs");
+    fs::write(
+        &test_file,
+        r#"
+verus! {
+
+spec fn bounded(x: int) -> bool {
+    0 <= x < 100
+}
+
+proof fn lemma_square(x: int) {
+    assert(x * x < 10000) by(nonlinear_arith)
+        requires bounded(x),
+    {
+    }
+}
+
+fn main() {
+    proof {
+        lemma_square(5);
+    }
+}
+
+}
+"#,
+    )
+    .unwrap();
+    let report_file = temp_dir.path().join("unused.txt");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_verus-etags"))
+        .arg("-o")
+        .arg(temp_dir.path().join("TAGS"))
+        .arg("--unused-report")
+        .arg(&report_file)
+        .arg(&test_file)
+        .output()
+        .expect("Failed to execute verus-etags");
+    assert!(output.status.success(), "verus-etags failed: {:?}", output);
+
+    let report = fs::read_to_string(&report_file).unwrap();
+    assert!(!report.contains("bounded"), "bounded is used in requires of assert by: {}", report);
+}
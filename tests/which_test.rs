@@ -0,0 +1,114 @@
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use tempfile::TempDir;
+
+const SOURCE: &str = "\
+mod seq_lib {
+    pub struct Seq {
+        len: u32,
+    }
+
+    impl Seq {
+        /// The length
+        pub fn len(&self) -> u32 {
+            self.len
+        }
+    }
+}
+
+fn main() {}
+";
+
+fn workspace() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("lib.rs"), SOURCE).unwrap();
+    temp_dir
+}
+
+#[test]
+fn test_positions_from_arguments() {
+    let temp_dir = workspace();
+    let output = Command::new(env!("CARGO_BIN_EXE_verus-etags"))
+        .current_dir(temp_dir.path())
+        .args(["which", "lib.rs:9", "lib.rs:7:9", "lib.rs:13"])
+        .output()
+        .expect("Failed to execute verus-etags");
+    assert!(output.status.success(), "verus-etags failed: {:?}", output);
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(
+        lines,
+        vec![
+            "lib.rs:9: mod seq_lib > impl Seq > fn len",
+            "lib.rs:7: mod seq_lib > impl Seq > fn len",
+            "lib.rs:13: (top level)",
+        ]
+    );
+}
+
+#[test]
+fn test_positions_from_a_log_on_stdin() {
+    let temp_dir = workspace();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_verus-etags"))
+        .current_dir(temp_dir.path())
+        .arg("which")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("Failed to execute verus-etags");
+    let log = "error: postcondition not satisfied\n   --> lib.rs:3:9\nnote: failed this postcondition (lib.rs:14)\n";
+    child.stdin.take().unwrap().write_all(log.as_bytes()).unwrap();
+
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout, "lib.rs:3: mod seq_lib > struct Seq\nlib.rs:14: fn main\n");
+}
+
+#[test]
+fn test_invalid_position_is_rejected() {
+    let temp_dir = workspace();
+    let output = Command::new(env!("CARGO_BIN_EXE_verus-etags"))
+        .current_dir(temp_dir.path())
+        .args(["which", "lib.rs"])
+        .output()
+        .expect("Failed to execute verus-etags");
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_impl_labels_keep_generics_and_trait() {
+    let temp_dir = TempDir::new().unwrap();
+    let source = "\
+struct Seq<A> {
+    items: Vec<A>,
+}
+
+impl<A> Seq<A> {
+    fn len(&self) -> usize {
+        self.items.len()
+    }
+}
+
+impl<A> Clone for Seq<A> where A: Clone {
+    fn clone(&self) -> Self {
+        Seq { items: self.items.clone() }
+    }
+}
+";
+    fs::write(temp_dir.path().join("lib.rs"), source).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_verus-etags"))
+        .current_dir(temp_dir.path())
+        .args(["which", "lib.rs:7", "lib.rs:13"])
+        .output()
+        .expect("Failed to execute verus-etags");
+    assert!(output.status.success(), "verus-etags failed: {:?}", output);
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(
+        stdout,
+        "lib.rs:7: impl Seq<A> > fn len\nlib.rs:13: impl Clone for Seq<A> > fn clone\n"
+    );
+}